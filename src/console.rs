@@ -0,0 +1,297 @@
+//! Backtick-toggled debug console. Only compiled in with the `dev` feature.
+
+use bevy::{input::InputSystem, prelude::*};
+
+use crate::{
+    spawn_myrrh, AtlasLayouts, CameraScript, CameraScriptStep, EndTurnEvent, Fonts, GameGrid,
+    GridPosition, Selectable, SpriteSheets, UnitNameRegistry, MYRRH_MOVEMENT_RANGE,
+};
+
+const USAGE: &str = "commands: spawn <unit> <x> <y> | kill <id> | pan <x> <y> | zoom <scale> \
+                     | hold <seconds> | endturn | help";
+const CAMERA_SCRIPT_STEP_SECONDS: f32 = 1.;
+const VISIBLE_HISTORY: usize = 8;
+
+#[derive(Debug, PartialEq)]
+pub enum ConsoleCommand {
+    Spawn { unit: String, pos: GridPosition },
+    Kill { id: u32 },
+    Pan { pos: GridPosition },
+    Zoom { tile_scale: u32 },
+    Hold { seconds: u32 },
+    EndTurn,
+    Help,
+}
+
+fn parse_number(token: &str) -> Result<u32, String> {
+    token
+        .parse()
+        .map_err(|_| format!("expected a number, got '{}'", token))
+}
+
+pub fn parse_command(line: &str) -> Result<ConsoleCommand, String> {
+    let tokens = line.split_whitespace().collect::<Vec<&str>>();
+
+    match tokens.as_slice() {
+        ["spawn", unit, x, y] => Ok(ConsoleCommand::Spawn {
+            unit: unit.to_string(),
+            pos: GridPosition {
+                x: parse_number(x)?,
+                y: parse_number(y)?,
+            },
+        }),
+        ["kill", id] => Ok(ConsoleCommand::Kill {
+            id: parse_number(id)?,
+        }),
+        ["pan", x, y] => Ok(ConsoleCommand::Pan {
+            pos: GridPosition {
+                x: parse_number(x)?,
+                y: parse_number(y)?,
+            },
+        }),
+        ["zoom", tile_scale] => Ok(ConsoleCommand::Zoom {
+            tile_scale: parse_number(tile_scale)?,
+        }),
+        ["hold", seconds] => Ok(ConsoleCommand::Hold {
+            seconds: parse_number(seconds)?,
+        }),
+        ["endturn"] => Ok(ConsoleCommand::EndTurn),
+        ["help"] => Ok(ConsoleCommand::Help),
+        _ => Err(format!("unknown command '{}', {}", line.trim(), USAGE)),
+    }
+}
+
+#[derive(Default)]
+pub struct Console {
+    open: bool,
+    input: String,
+    history: Vec<String>,
+}
+
+struct ConsoleText;
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Console>()
+            .add_event::<ConsoleCommand>()
+            .add_startup_system_to_stage(StartupStage::PostStartup, setup_console.system())
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                capture_console_input.system().after(InputSystem),
+            )
+            .add_system(dispatch_console_commands.system())
+            .add_system(update_console_text.system());
+    }
+}
+
+fn setup_console(mut commands: Commands, fonts: Res<Fonts>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(10.),
+                    left: Val::Px(10.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: fonts.ui.clone(),
+                    font_size: 16.,
+                    color: Color::WHITE,
+                },
+                TextAlignment::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(ConsoleText);
+}
+
+fn capture_console_input(
+    mut console: ResMut<Console>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut received_characters: EventReader<ReceivedCharacter>,
+    mut console_commands: EventWriter<ConsoleCommand>,
+) {
+    let typed = received_characters
+        .iter()
+        .map(|ev| ev.char)
+        .filter(|c| !c.is_control() && *c != '`')
+        .collect::<String>();
+
+    if keyboard_input.just_pressed(KeyCode::Grave) {
+        console.open = !console.open;
+        keyboard_input.reset(KeyCode::Grave);
+        return;
+    }
+
+    if !console.open {
+        return;
+    }
+
+    console.input.push_str(&typed);
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        let line = std::mem::take(&mut console.input);
+        if !line.trim().is_empty() {
+            console.history.push(format!("> {}", line));
+            match parse_command(&line) {
+                Ok(command) => console_commands.send(command),
+                Err(message) => console.history.push(message),
+            }
+        }
+    }
+
+    // Keys typed into the console shouldn't also move the camera, end the turn, etc.
+    let keys = keyboard_input
+        .get_pressed()
+        .chain(keyboard_input.get_just_released())
+        .cloned()
+        .collect::<Vec<KeyCode>>();
+    for key in keys {
+        keyboard_input.reset(key);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn dispatch_console_commands(
+    mut commands: Commands,
+    mut console: ResMut<Console>,
+    mut console_commands: EventReader<ConsoleCommand>,
+    mut end_turn: EventWriter<EndTurnEvent>,
+    sprite_sheets: Res<SpriteSheets>,
+    atlas_layouts: Res<AtlasLayouts>,
+    game_grid: Res<GameGrid>,
+    mut unit_name_registry: ResMut<UnitNameRegistry>,
+    mut camera_script: ResMut<CameraScript>,
+    unit_query: Query<(Entity, &GridPosition), With<Selectable>>,
+) {
+    for command in console_commands.iter() {
+        match command {
+            ConsoleCommand::Spawn { unit, pos } => {
+                if unit != "myrrh" {
+                    console.history.push(format!("unknown unit '{}'", unit));
+                } else if pos.x as usize >= game_grid.width || pos.y as usize >= game_grid.height {
+                    console.history.push("position is off the grid".to_string());
+                } else if unit_query.iter().any(|(_, p)| p == pos) {
+                    console.history.push("tile is occupied".to_string());
+                } else {
+                    let entity = spawn_myrrh(
+                        &mut commands,
+                        &sprite_sheets,
+                        &atlas_layouts.myrrh,
+                        *pos,
+                        MYRRH_MOVEMENT_RANGE,
+                        unit_name_registry.next_name("Myrrh"),
+                    );
+                    console
+                        .history
+                        .push(format!("spawned {} as {}", unit, entity.id()));
+                }
+            }
+            ConsoleCommand::Kill { id } => {
+                match unit_query.iter().find(|(entity, _)| entity.id() == *id) {
+                    Some((entity, _)) => {
+                        commands.entity(entity).despawn();
+                    }
+                    None => console.history.push(format!("no unit with id {}", id)),
+                }
+            }
+            ConsoleCommand::Pan { pos } => {
+                if pos.x as usize >= game_grid.width || pos.y as usize >= game_grid.height {
+                    console.history.push("position is off the grid".to_string());
+                } else {
+                    camera_script.steps.push_back(CameraScriptStep::PanTo {
+                        pos: *pos,
+                        seconds: CAMERA_SCRIPT_STEP_SECONDS,
+                    });
+                }
+            }
+            ConsoleCommand::Zoom { tile_scale } => {
+                camera_script.steps.push_back(CameraScriptStep::Zoom {
+                    tile_scale: *tile_scale as f32,
+                    seconds: CAMERA_SCRIPT_STEP_SECONDS,
+                });
+            }
+            ConsoleCommand::Hold { seconds } => {
+                camera_script.steps.push_back(CameraScriptStep::Hold {
+                    seconds: *seconds as f32,
+                });
+            }
+            ConsoleCommand::EndTurn => end_turn.send(EndTurnEvent),
+            ConsoleCommand::Help => console.history.push(USAGE.to_string()),
+        }
+    }
+}
+
+fn update_console_text(
+    console: Res<Console>,
+    mut console_text_query: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+
+    let contents = if console.open {
+        let start = console.history.len().saturating_sub(VISIBLE_HISTORY);
+        let mut lines = console.history[start..].to_vec();
+        lines.push(format!("> {}_", console.input));
+        lines.join("\n")
+    } else {
+        String::new()
+    };
+
+    for mut text in console_text_query.iter_mut() {
+        text.sections[0].value = contents.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_spawn() {
+        assert_eq!(
+            parse_command("spawn myrrh 3 5"),
+            Ok(ConsoleCommand::Spawn {
+                unit: "myrrh".to_string(),
+                pos: GridPosition { x: 3, y: 5 },
+            })
+        );
+    }
+
+    #[test]
+    fn parses_kill() {
+        assert_eq!(parse_command("kill 7"), Ok(ConsoleCommand::Kill { id: 7 }));
+    }
+
+    #[test]
+    fn parses_endturn() {
+        assert_eq!(parse_command("endturn"), Ok(ConsoleCommand::EndTurn));
+    }
+
+    #[test]
+    fn rejects_bad_number() {
+        assert_eq!(
+            parse_command("kill seven"),
+            Err("expected a number, got 'seven'".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_command_prints_usage() {
+        let err = parse_command("reveal").unwrap_err();
+        assert!(err.starts_with("unknown command 'reveal'"));
+        assert!(err.ends_with(USAGE));
+    }
+}
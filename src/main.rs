@@ -1,5 +1,12 @@
 #![feature(option_result_contains)]
 
+#[cfg(feature = "dev")]
+mod console;
+mod pathfinding;
+mod rules;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
 use bevy::{
     input::{mouse::MouseWheel, system::exit_on_esc_system},
     prelude::*,
@@ -15,13 +22,43 @@ impl GridPosition {
     fn dist(&self, p: &GridPosition) -> u32 {
         (i32::abs(self.x as i32 - p.x as i32) + i32::abs(self.y as i32 - p.y as i32)) as u32
     }
+
+    /// Sort key ordering positions by row, then column.
+    fn row_major_key(&self) -> (u32, u32) {
+        (self.y, self.x)
+    }
+
+    /// Row-major index of this position in `grid`, for dense per-tile storage.
+    ///
+    /// The position must be inside `grid`, an out of bounds `x` would alias a tile on
+    /// the next row.
+    fn to_index(&self, grid: &GameGrid) -> usize {
+        debug_assert!(
+            (self.x as usize) < grid.width && (self.y as usize) < grid.height,
+            "({}, {}) is outside the grid",
+            self.x,
+            self.y
+        );
+        self.y as usize * grid.width + self.x as usize
+    }
+
+    fn from_index(index: usize, grid: &GameGrid) -> GridPosition {
+        GridPosition {
+            x: (index % grid.width) as u32,
+            y: (index / grid.width) as u32,
+        }
+    }
 }
 
-#[derive(PartialEq)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 enum GridHighlightType {
     PlayerUnitMovement,
     PlayerHover,
     PlayerUnitSelected,
+    ClickFeedback,
+    Objective,
+    PathPreview,
+    PathPreviewOverBudget,
 }
 
 enum GridAnchorType {
@@ -43,6 +80,13 @@ struct GridUI {
 
 struct SelectedUnit;
 
+/// Marks a unit that has already been given an order this turn.
+struct HasActed;
+
+/// The unit most recently deselected without acting, so it can be recalled this turn.
+#[derive(Default)]
+struct LastSelected(Option<Entity>);
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 enum Turn {
     Player,
@@ -56,13 +100,134 @@ impl Default for Turn {
     }
 }
 
+impl Turn {
+    fn next(&self) -> Turn {
+        match self {
+            Turn::Player => Turn::Enemy,
+            Turn::Enemy => Turn::Neutral,
+            Turn::Neutral => Turn::Player,
+        }
+    }
+}
+
+struct EndTurnEvent;
+
+struct TurnStartEvent {
+    turn: Turn,
+    turn_number: u32,
+}
+
+/// "Did X happen this turn" flags, cleared whenever a new turn starts.
+#[derive(Default)]
+struct TurnFlags {
+    flags: HashMap<String, bool>,
+}
+
+impl TurnFlags {
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn set(&mut self, flag: &str) {
+        self.flags.insert(flag.to_string(), true);
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn is_set(&self, flag: &str) -> bool {
+        self.flags.get(flag).copied().unwrap_or(false)
+    }
+
+    fn clear(&mut self) {
+        self.flags.clear();
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+enum TurnMode {
+    WholeFaction,
+    #[cfg_attr(not(test), allow(dead_code))]
+    Initiative,
+}
+
+impl Default for TurnMode {
+    fn default() -> Self {
+        TurnMode::WholeFaction
+    }
+}
+
+#[derive(Default)]
 struct TurnState {
     turn: Turn,
+    turn_number: u32,
+    mode: TurnMode,
+    active_unit: Option<Entity>,
+}
+
+impl TurnState {
+    /// In initiative mode only the unit whose turn it is may be selected for action,
+    /// in whole-faction mode any unit can be.
+    fn can_select(&self, entity: Entity) -> bool {
+        match self.mode {
+            TurnMode::WholeFaction => true,
+            TurnMode::Initiative => self.active_unit.contains(&entity),
+        }
+    }
 }
 
+/// `was_handled` describes the most recent click, `this_frame` whether that click
+/// happened this frame so stale clicks aren't acted on again.
 #[derive(Default)]
 struct LastClick {
     was_handled: bool,
+    this_frame: bool,
+}
+
+struct ClickRipple {
+    timer: Timer,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+enum RejectReason {
+    OutOfRange,
+    TileOccupied,
+    AlreadyActed,
+    NotActiveUnit,
+}
+
+impl RejectReason {
+    fn message(&self) -> &'static str {
+        match self {
+            RejectReason::OutOfRange => "Out of range",
+            RejectReason::TileOccupied => "Tile occupied",
+            RejectReason::AlreadyActed => "Already acted this turn",
+            RejectReason::NotActiveUnit => "Not this unit's turn",
+        }
+    }
+}
+
+/// Sent whenever the player tries something the rules don't allow.
+struct RejectEvent {
+    reason: RejectReason,
+}
+
+struct Toast {
+    timer: Timer,
+}
+
+#[derive(Default)]
+struct GameRules {
+    queue_clicks_during_animation: bool,
+    turn_time_limit: Option<f32>,
+}
+
+/// Seconds left in the player's turn when `GameRules::turn_time_limit` is set.
+struct TurnTimer {
+    remaining: f32,
+}
+
+struct TurnTimerText;
+
+/// A click made while a unit was animating, replayed once the animation ends.
+#[derive(Default)]
+struct QueuedClick {
+    position: Option<Vec2>,
 }
 
 struct GridHighlight {
@@ -70,9 +235,126 @@ struct GridHighlight {
     highlight_type: GridHighlightType,
 }
 
+/// Tiles covered by each kind of highlight, rebuilt once a frame by `build_highlight_index`
+/// and shared by the tile and object render passes.
+#[derive(Default)]
+struct HighlightIndex {
+    selected: HashSet<GridPosition>,
+    movement: HashSet<GridPosition>,
+    hover: HashSet<GridPosition>,
+    objective: HashSet<GridPosition>,
+    path_preview: HashSet<GridPosition>,
+    path_preview_over_budget: HashSet<GridPosition>,
+}
+
+impl HighlightIndex {
+    /// Whether an object on `pos` should be raised to the movement highlight z-level.
+    fn raises_to_movement_z(&self, pos: &GridPosition) -> bool {
+        self.movement.contains(pos)
+            || self.hover.contains(pos)
+            || self.path_preview.contains(pos)
+            || self.path_preview_over_budget.contains(pos)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+enum Objective {
+    ReachTile(GridPosition),
+}
+
+struct ScenarioObjective {
+    objective: Objective,
+    completed: bool,
+}
+
+#[derive(Default)]
+struct Scenario {
+    objectives: Vec<ScenarioObjective>,
+}
+
+struct Reinforcement {
+    turn_number: u32,
+    template: String,
+    pos: GridPosition,
+}
+
+/// Units that join the player at the start of a given turn.
+#[derive(Default)]
+struct Reinforcements {
+    schedule: Vec<Reinforcement>,
+    /// Whether units have to wait a turn before acting after they arrive.
+    arrive_exhausted: bool,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Difficulty {
+    #[cfg_attr(not(test), allow(dead_code))]
+    Easy,
+    Normal,
+    #[cfg_attr(not(test), allow(dead_code))]
+    Hard,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty::Normal
+    }
+}
+
+struct LoadoutUnit {
+    template: String,
+    pos: GridPosition,
+    movement_range: u32,
+}
+
+/// The units the player starts the map with, spawned by `spawn_units`.
+struct PartyLoadout {
+    units: Vec<LoadoutUnit>,
+}
+
+impl PartyLoadout {
+    /// Harder difficulties field fewer units that move less.
+    fn for_difficulty(difficulty: Difficulty) -> Self {
+        let myrrh = |x, y, movement_range| LoadoutUnit {
+            template: "Myrrh".to_string(),
+            pos: GridPosition { x, y },
+            movement_range,
+        };
+
+        let units = match difficulty {
+            Difficulty::Easy => vec![myrrh(4, 4, 4), myrrh(6, 4, 4)],
+            Difficulty::Normal => vec![myrrh(4, 4, 3)],
+            Difficulty::Hard => vec![myrrh(4, 4, 2)],
+        };
+
+        PartyLoadout { units }
+    }
+}
+
 struct GameGrid {
     width: usize,
     height: usize,
+    /// Tiles that are only drawn and never hovered or clicked, e.g. a border frame.
+    decorative_tiles: Vec<GridPosition>,
+}
+
+/// Per-map presentation: what's drawn behind the grid, a tint over it and its music.
+struct MapAmbient {
+    clear_color: Color,
+    tint: Color,
+    background: Option<String>,
+    music: Option<String>,
+}
+
+impl Default for MapAmbient {
+    fn default() -> Self {
+        MapAmbient {
+            clear_color: Color::rgb(0.4, 0.4, 0.4),
+            tint: Color::WHITE,
+            background: None,
+            music: None,
+        }
+    }
 }
 
 #[derive(Default)]
@@ -132,6 +414,18 @@ struct IdleAnimation {
     animation: Option<AnimationRange>,
     should_loop: bool,
     timer: Timer,
+    /// Squash and stretch the sprite slightly between frames so short loops look less choppy.
+    interpolate: bool,
+}
+
+/// Horizontal and vertical scale multipliers for an interpolated idle animation `progress`
+/// of the way through a frame, easing out of and back into the unscaled sprite so there's no
+/// jump when the frame changes.
+fn idle_squash(progress: f32) -> Vec2 {
+    const SQUASH_AMOUNT: f32 = 0.04;
+
+    let t = (1. - (progress * std::f32::consts::PI * 2.).cos()) / 2.;
+    Vec2::new(1. + SQUASH_AMOUNT * t, 1. - SQUASH_AMOUNT * t)
 }
 
 #[derive(Default)]
@@ -139,6 +433,65 @@ struct SelectedAnimation {
     animation: Option<AnimationRange>,
     should_loop: bool,
     timer: Timer,
+    /// Looping range to switch to once a non-looping `animation` has played through.
+    chained: Option<AnimationRange>,
+    playing_chained: bool,
+}
+
+impl SelectedAnimation {
+    fn restart(&mut self) {
+        self.playing_chained = false;
+        if let Some(animation) = self.animation.as_mut() {
+            animation.reset();
+        }
+        if let Some(chained) = self.chained.as_mut() {
+            chained.reset();
+        }
+    }
+}
+
+enum ActionStep {
+    Walk(GridPosition),
+    Animate(AnimationRange),
+}
+
+/// Ordered queue of steps a unit plays through one at a time, each step starting
+/// once the previous one has sent its `AnimationFinished`.
+#[derive(Default)]
+struct ActionSequence {
+    steps: VecDeque<ActionStep>,
+    in_progress: bool,
+}
+
+impl ActionSequence {
+    fn new(steps: Vec<ActionStep>) -> Self {
+        ActionSequence {
+            steps: steps.into(),
+            in_progress: false,
+        }
+    }
+}
+
+struct MovementTween {
+    from: GridPosition,
+    timer: Timer,
+    easing: Easing,
+}
+
+/// Pulsing marker on the tile `unit` is walking to, removed once it arrives.
+struct DestinationMarker {
+    unit: Entity,
+    pos: GridPosition,
+    timer: Timer,
+}
+
+struct ActionAnimation {
+    animation: AnimationRange,
+    timer: Timer,
+}
+
+struct AnimationFinished {
+    entity: Entity,
 }
 
 #[derive(Default)]
@@ -191,6 +544,16 @@ struct GridTile {
     hoverable: Hoverable,
 }
 
+/// A grid tile without the mouse interaction components, so the interaction systems skip it.
+#[derive(Bundle, Default)]
+struct DecorativeTile {
+    grid_pos: GridPosition,
+    #[bundle]
+    sprite: SpriteSheetBundle,
+    sprite_size: SpriteSize,
+    grid_tile_tag: GridTileTag,
+}
+
 struct MovementRange {
     range: u32,
     flying: bool,
@@ -198,6 +561,127 @@ struct MovementRange {
 
 struct Selectable;
 
+struct UnitName {
+    name: String,
+}
+
+/// Hands out default names per unit template: the first is just the template name,
+/// later ones are numbered ("Myrrh", "Myrrh 2", ...).
+#[derive(Default)]
+struct UnitNameRegistry {
+    spawned: HashMap<String, u32>,
+}
+
+impl UnitNameRegistry {
+    fn next_name(&mut self, template: &str) -> UnitName {
+        let count = self.spawned.entry(template.to_string()).or_insert(0);
+        *count += 1;
+
+        let name = if *count == 1 {
+            template.to_string()
+        } else {
+            format!("{} {}", template, count)
+        };
+
+        UnitName { name }
+    }
+}
+
+struct UnitNameLabel {
+    unit: Entity,
+}
+
+/// Sidebar listing the player's units.
+struct RosterPanel;
+
+/// A button in the roster that selects `unit` when clicked.
+struct RosterEntry {
+    unit: Entity,
+}
+
+struct RosterEntryText {
+    unit: Entity,
+}
+
+struct RosterMaterials {
+    entry: Handle<ColorMaterial>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+enum Facing {
+    East = 0,
+    NorthEast = 1,
+    North = 2,
+    NorthWest = 3,
+    West = 4,
+    SouthWest = 5,
+    South = 6,
+    SouthEast = 7,
+}
+
+impl Default for Facing {
+    fn default() -> Self {
+        Facing::South
+    }
+}
+
+impl Facing {
+    const ALL: [Facing; 8] = [
+        Facing::East,
+        Facing::NorthEast,
+        Facing::North,
+        Facing::NorthWest,
+        Facing::West,
+        Facing::SouthWest,
+        Facing::South,
+        Facing::SouthEast,
+    ];
+
+    /// Maps a movement delta to the nearest of the eight directions, `None` if there was no movement.
+    fn from_delta(dx: i32, dy: i32) -> Option<Self> {
+        if dx == 0 && dy == 0 {
+            return None;
+        }
+
+        let angle = (dy as f32).atan2(dx as f32);
+        let octant = (angle / std::f32::consts::FRAC_PI_4).round() as i32;
+
+        Some(Facing::ALL[octant.rem_euclid(8) as usize])
+    }
+
+    /// The directions to try, in order, when looking up frames for this facing.
+    /// Diagonals fall back to their horizontal then vertical neighbour.
+    fn fallbacks(&self) -> Vec<Facing> {
+        use Facing::*;
+        match self {
+            NorthEast => vec![NorthEast, East, North],
+            NorthWest => vec![NorthWest, West, North],
+            SouthWest => vec![SouthWest, West, South],
+            SouthEast => vec![SouthEast, East, South],
+            _ => vec![*self],
+        }
+    }
+}
+
+#[derive(Default, Copy, Clone)]
+struct FacingAnimationTable {
+    frames: [Option<AnimationRange>; 8],
+}
+
+impl FacingAnimationTable {
+    fn get(&self, facing: Facing) -> Option<AnimationRange> {
+        facing
+            .fallbacks()
+            .into_iter()
+            .find_map(|f| self.frames[f as usize])
+    }
+}
+
+#[derive(Default)]
+struct FacingAnimations {
+    idle: FacingAnimationTable,
+}
+
 #[derive(Bundle)]
 struct PlayerUnit {
     #[bundle]
@@ -216,118 +700,614 @@ struct SpriteSheets {
     myrrh: Handle<TextureAtlas>,
 }
 
-struct RenderSettings {
-    tile_size: f32,
-    tile_scale: f32,
-    camera_offset: Vec2,
+/// How a sprite sheet image is cut into frames.
+#[derive(Clone)]
+struct AtlasLayout {
+    path: &'static str,
+    tile_width: u32,
+    tile_height: u32,
+    columns: usize,
+    rows: usize,
 }
 
-fn main() {
-    App::build()
-        .add_startup_system(setup.system())
-        .insert_resource(WindowDescriptor {
-            title: "Rturn".to_string(),
-            width: 1200.,
-            height: 800.,
-            ..Default::default()
-        })
-        .add_plugins(DefaultPlugins)
-        .add_startup_stage(
-            "texture_setup",
-            SystemStage::single(setup_textures.system()),
-        )
-        .add_startup_stage(
-            "world_setup",
-            SystemStage::parallel()
-                .with_system(setup_grid_tiles.system())
-                .with_system(spawn_units.system()),
-        )
-        .add_system(move_camera.system())
-        .add_system(handle_mouse_interactions.system().label("mouse_input"))
-        .add_system(handle_hover_sprite_change.system().after("mouse_input"))
-        .add_system(
-            handle_player_unit_selection_grid_highlights
-                .system()
-                .label("unit_selection_grid_highlights")
-                .after("unit_selection"),
-        )
-        .add_system(
-            handle_player_unit_selection_movement_highlights
-                .system()
-                .label("unit_selection_movment_highlights")
-                .after("unit_selection"),
-        )
-        .add_system(
-            handle_unit_selection
-                .system()
-                .label("unit_selection")
-                .after("mouse_input")
-                .after("handle_grid_clicks"),
-        )
-        .add_system(
-            handle_hover_grid_highlights
-                .system()
-                .label("grid_hover_highlight")
-                .after("mouse_input"),
-        )
-        .add_system(
-            render_grid_tiles
-                .system()
-                .after("unit_selection_grid_highlights")
-                .after("unit_selection_movment_highlights"),
+impl AtlasLayout {
+    fn expected_size(&self) -> (u32, u32) {
+        (
+            self.tile_width * self.columns as u32,
+            self.tile_height * self.rows as u32,
         )
-        .add_system(handle_grid_clicks.system().label("handle_grid_clicks"))
-        .add_system(exit_on_esc_system.system())
-        .add_system_set_to_stage(
-            CoreStage::PostUpdate,
-            SystemSet::new()
-                .with_system(render_grid_objects.system().label("render_grid_objects"))
-                .with_system(animate_idle.system().after("render_grid_objects"))
-                .with_system(animate_selected.system().after("render_grid_objects")),
+    }
+
+    fn build(&self, texture: Handle<Texture>) -> TextureAtlas {
+        TextureAtlas::from_grid(
+            texture,
+            Vec2::new(self.tile_width as f32, self.tile_height as f32),
+            self.columns,
+            self.rows,
         )
-        .run();
+    }
 }
 
-fn setup(mut commands: Commands) {
-    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
-    commands.insert_resource(GameGrid {
-        width: 16,
-        height: 16,
-    });
-    commands.insert_resource(RenderSettings {
-        tile_size: 64.,
-        tile_scale: 2.,
+struct AtlasLayouts {
+    grid: AtlasLayout,
+    myrrh: AtlasLayout,
+}
+
+impl Default for AtlasLayouts {
+    fn default() -> Self {
+        AtlasLayouts {
+            grid: AtlasLayout {
+                path: "textures/grid.png",
+                tile_width: 32,
+                tile_height: 32,
+                columns: 4,
+                rows: 2,
+            },
+            myrrh: AtlasLayout {
+                path: "textures/myrrh.png",
+                tile_width: 128,
+                tile_height: 128,
+                columns: 3,
+                rows: 3,
+            },
+        }
+    }
+}
+
+/// Sprite sheet images still loading, checked against their layout once they arrive.
+#[derive(Default)]
+struct PendingAtlasChecks {
+    textures: Vec<(Handle<Texture>, AtlasLayout)>,
+}
+
+struct Fonts {
+    ui: Handle<Font>,
+}
+
+struct StatSheet;
+
+#[derive(Copy, Clone)]
+struct RenderSettings {
+    tile_size: f32,
+    tile_scale: f32,
+    /// Size in texels of one tile in the grid sprite sheet.
+    tile_texture_size: f32,
+    /// Thickness in texels of the border drawn around each tile in the grid sprite sheet.
+    tile_border: f32,
+    camera_offset: Vec2,
+    tile_gap: f32,
+    cull_offscreen_tiles: bool,
+    show_unit_names: bool,
+}
+
+impl RenderSettings {
+    /// Distance between the centres of two adjacent tiles, including any gap.
+    ///
+    /// A tile is drawn `tile_size * tile_scale` wide, but the tile art has a border
+    /// `tile_border` texels thick on every edge. Neighbouring tiles are pulled together by
+    /// one border width so their borders overlap into a single line instead of doubling
+    /// up, which for the 2 texel border on the 32 texel grid art is 1/16th of a tile.
+    fn tile_stride(&self) -> f32 {
+        let rendered_tile_size = self.tile_size * self.tile_scale;
+        let border_overlap = rendered_tile_size * self.tile_border / self.tile_texture_size;
+
+        rendered_tile_size - border_overlap + self.tile_gap * self.tile_scale
+    }
+}
+
+const MIN_TILE_SCALE: f32 = 1.;
+const MAX_TILE_SCALE: f32 = 10.;
+
+/// Keeps the grid under the centre of the screen for a camera at `offset` and `tile_scale`.
+fn clamp_camera_offset(
+    offset: Vec2,
+    tile_scale: f32,
+    render_settings: &RenderSettings,
+    game_grid: &GameGrid,
+) -> Vec2 {
+    let stride = RenderSettings {
+        tile_scale,
+        ..*render_settings
+    }
+    .tile_stride();
+
+    let max_x = (game_grid.width.max(1) - 1) as f32 * stride;
+    let max_y = (game_grid.height.max(1) - 1) as f32 * stride;
+
+    Vec2::new(offset.x.max(-max_x).min(0.), offset.y.max(-max_y).min(0.))
+}
+
+/// Inclusive bottom-left and top-right corners of the tiles overlapping a viewport of
+/// `window_size` centred on the camera, `None` if no tile is on screen.
+fn visible_tile_rect(
+    render_settings: &RenderSettings,
+    window_size: Vec2,
+    game_grid: &GameGrid,
+) -> Option<(GridPosition, GridPosition)> {
+    if game_grid.width == 0 || game_grid.height == 0 {
+        return None;
+    }
+
+    let stride = render_settings.tile_stride();
+    let tile_half_extent = render_settings.tile_size * render_settings.tile_scale / 2.;
+    let half_window = window_size / 2.;
+
+    let axis_range = |offset: f32, half_window: f32, len: usize| {
+        let min = ((-half_window - tile_half_extent - offset) / stride)
+            .ceil()
+            .max(0.);
+        let max = ((half_window + tile_half_extent - offset) / stride)
+            .floor()
+            .min((len - 1) as f32);
+        if min > max {
+            None
+        } else {
+            Some((min as u32, max as u32))
+        }
+    };
+
+    let (min_x, max_x) = axis_range(
+        render_settings.camera_offset.x,
+        half_window.x,
+        game_grid.width,
+    )?;
+    let (min_y, max_y) = axis_range(
+        render_settings.camera_offset.y,
+        half_window.y,
+        game_grid.height,
+    )?;
+
+    Some((
+        GridPosition { x: min_x, y: min_y },
+        GridPosition { x: max_x, y: max_y },
+    ))
+}
+
+#[derive(Default)]
+struct CameraBookmarks {
+    slots: [Option<(Vec2, f32)>; 4],
+}
+
+#[derive(Default)]
+struct CameraTransition {
+    target: Option<(Vec2, f32)>,
+}
+
+// Zoom and Hold are only queued from the dev console until something scripts cutscenes.
+#[cfg_attr(not(feature = "dev"), allow(dead_code))]
+#[derive(Copy, Clone, Debug)]
+enum CameraScriptStep {
+    /// Centre the camera on `pos`.
+    PanTo {
+        pos: GridPosition,
+        seconds: f32,
+    },
+    /// Zoom to `tile_scale`, keeping the same tile in the centre of the screen.
+    Zoom {
+        tile_scale: f32,
+        seconds: f32,
+    },
+    Hold {
+        seconds: f32,
+    },
+}
+
+struct CameraScriptMove {
+    from: (Vec2, f32),
+    to: (Vec2, f32),
+    timer: Timer,
+}
+
+/// Camera moves played back one after another for intros and scripted events. Manual
+/// camera control and clicks are ignored until it runs out of steps.
+#[derive(Default)]
+struct CameraScript {
+    steps: VecDeque<CameraScriptStep>,
+    current: Option<CameraScriptMove>,
+}
+
+impl CameraScript {
+    fn is_running(&self) -> bool {
+        self.current.is_some() || !self.steps.is_empty()
+    }
+}
+
+/// Camera offset that puts `pos` in the centre of the screen at `tile_scale`.
+fn centered_camera_offset(
+    pos: &GridPosition,
+    tile_scale: f32,
+    render_settings: &RenderSettings,
+    game_grid: &GameGrid,
+) -> Vec2 {
+    let stride = RenderSettings {
+        tile_scale,
+        ..*render_settings
+    }
+    .tile_stride();
+
+    clamp_camera_offset(
+        -Vec2::new(pos.x as f32, pos.y as f32) * stride,
+        tile_scale,
+        render_settings,
+        game_grid,
+    )
+}
+
+fn tile_to_world(pos: &GridPosition, render_settings: &RenderSettings) -> Vec2 {
+    let stride = render_settings.tile_stride();
+    render_settings.camera_offset + Vec2::new(pos.x as f32 * stride, pos.y as f32 * stride)
+}
+
+/// Inverse of `tile_to_world`, `None` for points outside `game_grid` or inside a gap.
+#[cfg_attr(not(test), allow(dead_code))]
+fn world_to_tile(
+    world: Vec2,
+    render_settings: &RenderSettings,
+    game_grid: &GameGrid,
+) -> Option<GridPosition> {
+    let stride = render_settings.tile_stride();
+    let local = (world - render_settings.camera_offset) / stride;
+    let (x, y) = (local.x.round(), local.y.round());
+
+    if x < 0. || y < 0. || x >= game_grid.width as f32 || y >= game_grid.height as f32 {
+        return None;
+    }
+
+    let pos = GridPosition {
+        x: x as u32,
+        y: y as u32,
+    };
+
+    let half_extent = (stride - render_settings.tile_gap * render_settings.tile_scale) / 2.;
+    let offset = world - tile_to_world(&pos, render_settings);
+    if offset.x.abs() > half_extent || offset.y.abs() > half_extent {
+        return None;
+    }
+
+    Some(pos)
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Easing {
+    #[cfg_attr(not(test), allow(dead_code))]
+    Linear,
+    EaseInOut,
+}
+
+impl Easing {
+    fn apply(&self, t: f32) -> f32 {
+        let t = t.max(0.).min(1.);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => t * t * (3. - 2. * t),
+        }
+    }
+}
+
+/// World position `t` of the way from the centre of `from` to the centre of `to`.
+fn lerp_tile_positions(
+    from: GridPosition,
+    to: GridPosition,
+    t: f32,
+    easing: Easing,
+    render_settings: &RenderSettings,
+) -> Vec2 {
+    let from = tile_to_world(&from, render_settings);
+    let to = tile_to_world(&to, render_settings);
+    from.lerp(to, easing.apply(t))
+}
+
+fn main() {
+    let mut app = App::build();
+    app.add_startup_system(setup.system())
+        .insert_resource(WindowDescriptor {
+            title: "Rturn".to_string(),
+            width: 1200.,
+            height: 800.,
+            ..Default::default()
+        })
+        .add_plugins(DefaultPlugins)
+        .add_event::<AnimationFinished>()
+        .add_event::<EndTurnEvent>()
+        .add_event::<TurnStartEvent>()
+        .add_event::<RejectEvent>()
+        .add_startup_stage(
+            "texture_setup",
+            SystemStage::single(setup_textures.system()),
+        )
+        // Runs before world_setup, the first stage whose systems need these resources.
+        .add_startup_stage(
+            "resource_check",
+            SystemStage::single(check_required_resources.system()),
+        )
+        .add_startup_stage(
+            "world_setup",
+            SystemStage::parallel()
+                .with_system(setup_grid_tiles.system())
+                .with_system(spawn_units.system())
+                .with_system(setup_ui.system())
+                .with_system(apply_map_clear_color.system())
+                .with_system(apply_map_ambient.system())
+                .with_system(announce_opening_turn.system()),
+        )
+        .add_system(move_camera.system().label("move_camera"))
+        .add_system(handle_camera_bookmarks.system().after("move_camera"))
+        .add_system(lerp_camera.system().after("move_camera"))
+        .add_system(run_camera_script.system().after("move_camera"))
+        .add_system(handle_mouse_interactions.system().label("mouse_input"))
+        .add_system(handle_hover_sprite_change.system().after("mouse_input"))
+        .add_system(
+            handle_player_unit_selection_grid_highlights
+                .system()
+                .label("unit_selection_grid_highlights")
+                .after("handle_clicks"),
+        )
+        .add_system(
+            handle_player_unit_selection_movement_highlights
+                .system()
+                .label("unit_selection_movment_highlights")
+                .after("handle_clicks"),
+        )
+        .add_system(
+            handle_clicks
+                .system()
+                .label("handle_clicks")
+                .after("mouse_input"),
+        )
+        .add_system(
+            handle_hover_grid_highlights
+                .system()
+                .label("grid_hover_highlight")
+                .after("mouse_input"),
+        )
+        .add_system(
+            handle_path_preview_highlights
+                .system()
+                .label("path_preview_highlights")
+                .after("mouse_input")
+                .after("handle_clicks"),
+        )
+        .add_system(expire_click_ripples.system())
+        .add_system(show_reject_toasts.system().after("handle_clicks"))
+        .add_system(expire_toasts.system())
+        .add_system(update_objectives.system().label("objectives"))
+        .add_system(
+            handle_objective_grid_highlights
+                .system()
+                .label("objective_grid_highlights")
+                .after("objectives"),
+        )
+        .add_system(update_stat_sheet.system().after("mouse_input"))
+        .add_system(spawn_unit_name_labels.system())
+        .add_system(toggle_unit_names.system())
+        .add_system(spawn_roster_entries.system())
+        .add_system(update_roster_entries.system())
+        .add_system(
+            handle_roster_clicks
+                .system()
+                .after("mouse_input")
+                .after("handle_clicks"),
+        )
+        .add_system(handle_end_turn_input.system().before("advance_turn"))
+        .add_system(tick_turn_timer.system().before("advance_turn"))
+        .add_system(pass_empty_turns.system().before("advance_turn"))
+        .add_system(advance_turn.system().label("advance_turn"))
+        .add_system(start_turn.system().after("advance_turn"))
+        .add_system(spawn_reinforcements.system().after("advance_turn"))
+        .add_system(update_turn_timer_text.system().after("advance_turn"))
+        .add_system(
+            recall_last_selected
+                .system()
+                .label("recall_last_selected")
+                .after("handle_clicks"),
+        )
+        .add_system(
+            process_action_sequence
+                .system()
+                .label("action_sequence")
+                .after("handle_clicks"),
+        )
+        .add_system(animate_movement_tween.system().after("action_sequence"))
+        .add_system(update_facing_animations.system().after("action_sequence"))
+        .add_system(check_atlas_dimensions.system())
+        .add_system(exit_on_esc_system.system())
+        .add_system_set_to_stage(
+            CoreStage::PostUpdate,
+            SystemSet::new()
+                .with_system(build_highlight_index.system().label("highlight_index"))
+                .with_system(render_grid_tiles.system().after("highlight_index"))
+                .with_system(
+                    render_grid_objects
+                        .system()
+                        .label("render_grid_objects")
+                        .after("highlight_index"),
+                )
+                .with_system(
+                    animate_idle
+                        .system()
+                        .label("animate_units")
+                        .after("render_grid_objects"),
+                )
+                .with_system(
+                    animate_selected
+                        .system()
+                        .label("animate_units")
+                        .after("render_grid_objects"),
+                )
+                .with_system(
+                    animate_action
+                        .system()
+                        .label("animate_units")
+                        .after("render_grid_objects"),
+                )
+                .with_system(tint_units.system().after("animate_units"))
+                .with_system(animate_destination_markers.system())
+                .with_system(track_last_selected.system())
+                .with_system(
+                    update_unit_name_labels
+                        .system()
+                        .after("render_grid_objects"),
+                ),
+        );
+
+    #[cfg(feature = "dev")]
+    app.add_plugin(console::ConsolePlugin);
+
+    app.run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
+    commands.spawn_bundle(UiCameraBundle::default());
+    commands.insert_resource(Fonts {
+        ui: asset_server.load("fonts/DejaVuSansMono.ttf"),
+    });
+    commands.insert_resource(GameGrid {
+        width: 16,
+        height: 16,
+        decorative_tiles: vec![],
+    });
+    commands.insert_resource(MapAmbient::default());
+    let difficulty = Difficulty::default();
+    commands.insert_resource(PartyLoadout::for_difficulty(difficulty));
+    commands.insert_resource(difficulty);
+    commands.insert_resource(Reinforcements {
+        arrive_exhausted: true,
+        ..Default::default()
+    });
+    let atlas_layouts = AtlasLayouts::default();
+    commands.insert_resource(RenderSettings {
+        tile_size: 64.,
+        tile_scale: 2.,
+        tile_texture_size: atlas_layouts.grid.tile_width as f32,
+        tile_border: 2.,
         camera_offset: Vec2::new(0., 0.),
+        tile_gap: 0.,
+        cull_offscreen_tiles: true,
+        show_unit_names: true,
     });
+    commands.insert_resource(atlas_layouts);
     commands.insert_resource(LastClick::default());
-    commands.insert_resource(TurnState { turn: Turn::Player });
+    commands.insert_resource(HighlightIndex::default());
+    commands.insert_resource(Scenario {
+        objectives: vec![ScenarioObjective {
+            objective: Objective::ReachTile(GridPosition { x: 12, y: 12 }),
+            completed: false,
+        }],
+    });
+    let game_rules = GameRules::default();
+    commands.insert_resource(TurnTimer {
+        remaining: game_rules.turn_time_limit.unwrap_or(0.),
+    });
+    commands.insert_resource(game_rules);
+    commands.insert_resource(QueuedClick::default());
+    commands.insert_resource(LastSelected::default());
+    commands.insert_resource(TurnFlags::default());
+    commands.insert_resource(UnitNameRegistry::default());
+    commands.insert_resource(CameraBookmarks::default());
+    commands.insert_resource(CameraTransition::default());
+    commands.insert_resource(CameraScript::default());
+    commands.insert_resource(TurnState {
+        turn: Turn::Player,
+        turn_number: 1,
+        ..Default::default()
+    });
+}
+
+/// Panics naming every resource the world setup and main schedule need that the earlier
+/// startup stages didn't insert, instead of letting the first system to touch it fail
+/// somewhere less obvious.
+fn check_required_resources(
+    game_grid: Option<Res<GameGrid>>,
+    render_settings: Option<Res<RenderSettings>>,
+    sprite_sheets: Option<Res<SpriteSheets>>,
+    turn_state: Option<Res<TurnState>>,
+    last_click: Option<Res<LastClick>>,
+    fonts: Option<Res<Fonts>>,
+    game_rules: Option<Res<GameRules>>,
+) {
+    let missing = [
+        ("GameGrid", game_grid.is_none()),
+        ("RenderSettings", render_settings.is_none()),
+        ("SpriteSheets", sprite_sheets.is_none()),
+        ("TurnState", turn_state.is_none()),
+        ("LastClick", last_click.is_none()),
+        ("Fonts", fonts.is_none()),
+        ("GameRules", game_rules.is_none()),
+    ]
+    .iter()
+    .filter(|(_, missing)| *missing)
+    .map(|(name, _)| *name)
+    .collect::<Vec<&str>>();
+
+    if !missing.is_empty() {
+        panic!(
+            "Required resources were not inserted during startup: {}",
+            missing.join(", ")
+        );
+    }
 }
 
 fn setup_textures(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    atlas_layouts: Res<AtlasLayouts>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
 ) {
-    let grid_texture_handle = asset_server.load("textures/grid.png");
-    let grid_texture_atlas =
-        TextureAtlas::from_grid(grid_texture_handle, Vec2::new(32.0, 32.0), 4, 2);
-    let grid_texture_atlas_handle = texture_atlases.add(grid_texture_atlas);
+    let mut pending_checks = PendingAtlasChecks::default();
+    let mut load_atlas = |layout: &AtlasLayout| {
+        let texture_handle: Handle<Texture> = asset_server.load(layout.path);
+        pending_checks
+            .textures
+            .push((texture_handle.clone(), layout.clone()));
+        texture_atlases.add(layout.build(texture_handle))
+    };
 
-    let myrrh_texture_handle = asset_server.load("textures/myrrh.png");
-    let myrrh_texture_atlas =
-        TextureAtlas::from_grid(myrrh_texture_handle, Vec2::new(128.0, 128.0), 3, 3);
-    let myrrh_texture_atlas_handle = texture_atlases.add(myrrh_texture_atlas);
+    let grid_texture_atlas_handle = load_atlas(&atlas_layouts.grid);
+    let myrrh_texture_atlas_handle = load_atlas(&atlas_layouts.myrrh);
 
     commands.insert_resource(SpriteSheets {
         grid: grid_texture_atlas_handle,
         myrrh: myrrh_texture_atlas_handle,
     });
+    commands.insert_resource(pending_checks);
+}
+
+fn check_atlas_dimensions(
+    textures: Res<Assets<Texture>>,
+    mut pending_checks: ResMut<PendingAtlasChecks>,
+) {
+    pending_checks.textures.retain(|(handle, layout)| {
+        let texture = match textures.get(handle) {
+            Some(texture) => texture,
+            None => return true,
+        };
+
+        let size = (texture.size.width, texture.size.height);
+        if size != layout.expected_size() {
+            panic!(
+                "{} is {}x{} but its atlas layout ({} columns x {} rows of {}x{} tiles) expects {}x{}",
+                layout.path,
+                size.0,
+                size.1,
+                layout.columns,
+                layout.rows,
+                layout.tile_width,
+                layout.tile_height,
+                layout.expected_size().0,
+                layout.expected_size().1,
+            );
+        }
+
+        false
+    });
 }
 
 fn setup_grid_tiles(
     mut commands: Commands,
     sprite_sheets: Res<SpriteSheets>,
     game_grid: Res<GameGrid>,
+    render_settings: Res<RenderSettings>,
 ) {
     let sprite = SpriteSheetBundle {
         texture_atlas: sprite_sheets.grid.clone(),
@@ -343,89 +1323,296 @@ fn setup_grid_tiles(
             };
 
             let sprite = sprite.clone();
+            let sprite_size = SpriteSize::new(
+                render_settings.tile_texture_size,
+                render_settings.tile_texture_size,
+            );
 
-            commands.spawn_bundle(GridTile {
-                grid_pos,
-                sprite,
-                sprite_size: SpriteSize::new(32., 32.),
-                grid_tile_tag: GridTileTag {},
-                ..Default::default()
-            });
+            if game_grid.decorative_tiles.contains(&grid_pos) {
+                commands.spawn_bundle(DecorativeTile {
+                    grid_pos,
+                    sprite,
+                    sprite_size,
+                    grid_tile_tag: GridTileTag {},
+                });
+            } else {
+                commands.spawn_bundle(GridTile {
+                    grid_pos,
+                    sprite,
+                    sprite_size,
+                    grid_tile_tag: GridTileTag {},
+                    ..Default::default()
+                });
+            }
+        }
+    }
+}
+
+fn spawn_units(
+    mut commands: Commands,
+    sprite_sheets: Res<SpriteSheets>,
+    atlas_layouts: Res<AtlasLayouts>,
+    party_loadout: Res<PartyLoadout>,
+    mut unit_name_registry: ResMut<UnitNameRegistry>,
+) {
+    for unit in party_loadout.units.iter() {
+        match unit.template.as_str() {
+            "Myrrh" => {
+                spawn_myrrh(
+                    &mut commands,
+                    &sprite_sheets,
+                    &atlas_layouts.myrrh,
+                    unit.pos,
+                    unit.movement_range,
+                    unit_name_registry.next_name("Myrrh"),
+                );
+            }
+            template => warn!("unknown loadout template {}", template),
         }
     }
 }
 
-fn spawn_units(mut commands: Commands, sprite_sheets: Res<SpriteSheets>) {
+/// Movement range of a Myrrh that isn't part of the party loadout, like reinforcements.
+const MYRRH_MOVEMENT_RANGE: u32 = 3;
+
+/// Myrrh's sheet has a single idle loop, so every cardinal direction uses it and the
+/// diagonals fall back to those.
+fn myrrh_idle_facing_table() -> FacingAnimationTable {
+    let idle = Some(AnimationRange::from_start_end(0, 1));
+
+    let mut table = FacingAnimationTable::default();
+    for facing in [Facing::East, Facing::North, Facing::West, Facing::South].iter() {
+        table.frames[*facing as usize] = idle;
+    }
+    table
+}
+
+fn spawn_myrrh(
+    commands: &mut Commands,
+    sprite_sheets: &SpriteSheets,
+    atlas_layout: &AtlasLayout,
+    grid_pos: GridPosition,
+    movement_range: u32,
+    name: UnitName,
+) -> Entity {
     commands
         .spawn_bundle(PlayerUnit {
-            grid_entity: GridEntity {
-                grid_pos: GridPosition { x: 4, y: 4 },
-            },
+            grid_entity: GridEntity { grid_pos },
             sprite: SpriteSheetBundle {
                 texture_atlas: sprite_sheets.myrrh.clone(),
                 sprite: TextureAtlasSprite::new(0),
                 ..Default::default()
             },
-            sprite_size: SpriteSize::new_with_render_size(128., 128., 1.5),
+            sprite_size: SpriteSize::new_with_render_size(
+                atlas_layout.tile_width as f32,
+                atlas_layout.tile_height as f32,
+                1.5,
+            ),
             mouse_interactible: MouseInteractible::from_z(10),
             clickable: Clickable::default(),
             hoverable: Hoverable::default(),
             selectable: Selectable {},
         })
         .insert(MovementRange {
-            range: 3,
+            range: movement_range,
             flying: false,
         })
+        .insert(Facing::default())
+        .insert(FacingAnimations {
+            idle: myrrh_idle_facing_table(),
+        })
+        .insert(name)
         .insert(IdleAnimation {
             animation: Some(AnimationRange::from_start_end(0, 1)),
             should_loop: true,
             timer: Timer::from_seconds(0.2, true),
+            interpolate: true,
         })
         .insert(SelectedAnimation {
             animation: Some(AnimationRange::from_start_end(0, 7)),
             should_loop: false,
             timer: Timer::from_seconds(0.1, true),
+            // Settle back into breathing rather than freezing on the last frame.
+            chained: Some(AnimationRange::from_start_end(0, 1)),
+            ..Default::default()
+        })
+        .id()
+}
+
+fn apply_map_clear_color(mut commands: Commands, map_ambient: Res<MapAmbient>) {
+    commands.insert_resource(ClearColor(map_ambient.clear_color));
+}
+
+fn apply_map_ambient(
+    mut commands: Commands,
+    map_ambient: Res<MapAmbient>,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if let Some(background) = &map_ambient.background {
+        let texture_handle: Handle<Texture> = asset_server.load(background.as_str());
+        commands.spawn_bundle(SpriteBundle {
+            material: materials.add(texture_handle.into()),
+            transform: Transform::from_xyz(0., 0., 0.),
+            ..Default::default()
         });
+    }
+
+    if let Some(music) = &map_ambient.music {
+        audio.play(asset_server.load(music.as_str()));
+    }
+}
+
+fn setup_ui(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(10.),
+                    right: Val::Px(10.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: fonts.ui.clone(),
+                    font_size: 18.,
+                    color: Color::WHITE,
+                },
+                TextAlignment::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(StatSheet);
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(10.),
+                    left: Val::Px(10.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: fonts.ui.clone(),
+                    font_size: 24.,
+                    color: Color::WHITE,
+                },
+                TextAlignment::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(TurnTimerText);
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(50.),
+                    left: Val::Px(10.),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            material: materials.add(Color::NONE.into()),
+            ..Default::default()
+        })
+        .insert(RosterPanel);
+
+    commands.insert_resource(RosterMaterials {
+        entry: materials.add(Color::rgba(0.1, 0.1, 0.1, 0.8).into()),
+    });
 }
 
 fn render_grid_objects(
     render_settings: Res<RenderSettings>,
+    windows: Res<Windows>,
+    game_grid: Res<GameGrid>,
     mut q: Query<(
         &GridPosition,
         &SpriteSize,
         &mut Transform,
         Option<&GridEntity>,
         Option<&mut MouseInteractible>,
+        Option<&MovementTween>,
+        Option<&GridTileTag>,
+        Option<&mut Visible>,
     )>,
-    grid_highlight_query: Query<&GridHighlight>,
+    highlight_index: Res<HighlightIndex>,
 ) {
     let RenderSettings {
         tile_size,
         tile_scale,
-        camera_offset,
+        ..
     } = *render_settings;
 
-    let mut need_movement_z_level = vec![];
-    let mut need_selected_z_level = vec![];
+    let visible_tiles = match windows.get_primary() {
+        Some(window) if render_settings.cull_offscreen_tiles => visible_tile_rect(
+            &render_settings,
+            Vec2::new(window.width(), window.height()),
+            &game_grid,
+        ),
+        _ => Some((
+            GridPosition { x: 0, y: 0 },
+            GridPosition {
+                x: game_grid.width.saturating_sub(1) as u32,
+                y: game_grid.height.saturating_sub(1) as u32,
+            },
+        )),
+    };
 
-    for grid_highlight in grid_highlight_query.iter() {
-        use GridHighlightType::*;
-        match grid_highlight.highlight_type {
-            PlayerUnitSelected => {
-                need_selected_z_level.push(grid_highlight.pos);
+    for (
+        pos,
+        sprite_size,
+        mut transform,
+        grid_entity,
+        mouse_interactible,
+        movement_tween,
+        grid_tile_tag,
+        visible,
+    ) in q.iter_mut()
+    {
+        if grid_tile_tag.is_some() {
+            let on_screen = visible_tiles.map_or(false, |(min, max)| {
+                pos.x >= min.x && pos.x <= max.x && pos.y >= min.y && pos.y <= max.y
+            });
+
+            if let Some(mut visible) = visible {
+                if visible.is_visible != on_screen {
+                    visible.is_visible = on_screen;
+                }
             }
-            _ => {
-                need_movement_z_level.push(grid_highlight.pos);
+
+            // Off-screen tiles keep their old transform, so make sure their stale bounding
+            // box can't catch clicks meant for whatever is on screen there now.
+            if !on_screen {
+                if let Some(mut mouse_interactible) = mouse_interactible {
+                    mouse_interactible.bounding_box = Rect::default();
+                }
+                continue;
             }
         }
-    }
 
-    for (pos, sprite_size, mut transform, grid_entity, mouse_interactible) in q.iter_mut() {
         let z = if grid_entity.is_some() {
             10.
-        } else if need_selected_z_level.contains(pos) {
+        } else if highlight_index.selected.contains(pos) {
             9.
-        } else if need_movement_z_level.contains(pos) {
+        } else if highlight_index.raises_to_movement_z(pos) {
             5.
         } else {
             1.
@@ -434,11 +1621,17 @@ fn render_grid_objects(
         let x_scale = tile_size / sprite_size.x * tile_scale;
         let y_scale = tile_size / sprite_size.y * tile_scale;
 
-        let x_adjustment = pos.x as f32 * tile_size * tile_scale / 16.;
-        let y_adjustment = pos.y as f32 * tile_size * tile_scale / 16.;
-
-        let center_x = camera_offset.x + tile_size * tile_scale * pos.x as f32 - x_adjustment;
-        let center_y = camera_offset.y + tile_size * tile_scale * pos.y as f32 - y_adjustment;
+        let center = match movement_tween {
+            Some(movement_tween) => lerp_tile_positions(
+                movement_tween.from,
+                *pos,
+                movement_tween.timer.percent(),
+                movement_tween.easing,
+                &render_settings,
+            ),
+            None => tile_to_world(pos, &render_settings),
+        };
+        let (center_x, center_y) = (center.x, center.y);
 
         transform.translation = Vec3::new(center_x, center_y, z);
 
@@ -463,7 +1656,15 @@ fn move_camera(
     keyboard_input: Res<Input<KeyCode>>,
     mut ev_scroll: EventReader<MouseWheel>,
     mut render_settings: ResMut<RenderSettings>,
+    mut camera_transition: ResMut<CameraTransition>,
+    camera_script: Res<CameraScript>,
 ) {
+    if camera_script.is_running() {
+        return;
+    }
+
+    let camera_offset_before = render_settings.camera_offset;
+
     if keyboard_input.pressed(KeyCode::Left) {
         render_settings.camera_offset.x += 16.;
     }
@@ -478,11 +1679,140 @@ fn move_camera(
     }
 
     const MOUSE_SCROLL_SENSITIVITY: f32 = 0.2;
+    let mut scrolled = false;
     for ev in ev_scroll.iter() {
         render_settings.tile_scale += ev.y * MOUSE_SCROLL_SENSITIVITY;
 
-        render_settings.tile_scale = render_settings.tile_scale.max(1.);
-        render_settings.tile_scale = render_settings.tile_scale.min(10.);
+        render_settings.tile_scale = render_settings.tile_scale.max(MIN_TILE_SCALE);
+        render_settings.tile_scale = render_settings.tile_scale.min(MAX_TILE_SCALE);
+        scrolled = true;
+    }
+
+    // Manual camera control takes over from any bookmark transition in progress.
+    if scrolled || render_settings.camera_offset != camera_offset_before {
+        camera_transition.target = None;
+    }
+}
+
+fn handle_camera_bookmarks(
+    keyboard_input: Res<Input<KeyCode>>,
+    render_settings: Res<RenderSettings>,
+    game_grid: Res<GameGrid>,
+    mut camera_bookmarks: ResMut<CameraBookmarks>,
+    mut camera_transition: ResMut<CameraTransition>,
+    camera_script: Res<CameraScript>,
+) {
+    if camera_script.is_running() {
+        return;
+    }
+
+    const BOOKMARK_KEYS: [KeyCode; 4] =
+        [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4];
+
+    let shift_held =
+        keyboard_input.pressed(KeyCode::LShift) || keyboard_input.pressed(KeyCode::RShift);
+
+    for (slot, key) in BOOKMARK_KEYS.iter().enumerate() {
+        if !keyboard_input.just_pressed(*key) {
+            continue;
+        }
+
+        if shift_held {
+            camera_bookmarks.slots[slot] =
+                Some((render_settings.camera_offset, render_settings.tile_scale));
+        } else if let Some((offset, tile_scale)) = camera_bookmarks.slots[slot] {
+            let tile_scale = tile_scale.max(MIN_TILE_SCALE).min(MAX_TILE_SCALE);
+            let offset = clamp_camera_offset(offset, tile_scale, &render_settings, &game_grid);
+            camera_transition.target = Some((offset, tile_scale));
+        }
+    }
+}
+
+fn lerp_camera(
+    mut render_settings: ResMut<RenderSettings>,
+    mut camera_transition: ResMut<CameraTransition>,
+    time: Res<Time>,
+) {
+    const CAMERA_LERP_SPEED: f32 = 8.;
+
+    if let Some((target_offset, target_scale)) = camera_transition.target {
+        let t = (time.delta_seconds() * CAMERA_LERP_SPEED).min(1.);
+        render_settings.camera_offset = render_settings.camera_offset.lerp(target_offset, t);
+        render_settings.tile_scale += (target_scale - render_settings.tile_scale) * t;
+
+        if render_settings.camera_offset.distance(target_offset) < 0.5
+            && (render_settings.tile_scale - target_scale).abs() < 0.01
+        {
+            render_settings.camera_offset = target_offset;
+            render_settings.tile_scale = target_scale;
+            camera_transition.target = None;
+        }
+    }
+}
+
+fn run_camera_script(
+    mut camera_script: ResMut<CameraScript>,
+    mut camera_transition: ResMut<CameraTransition>,
+    mut render_settings: ResMut<RenderSettings>,
+    game_grid: Res<GameGrid>,
+    time: Res<Time>,
+) {
+    if camera_script.current.is_none() {
+        let step = match camera_script.steps.pop_front() {
+            Some(step) => step,
+            None => return,
+        };
+
+        camera_transition.target = None;
+
+        let from = (render_settings.camera_offset, render_settings.tile_scale);
+        let (to, seconds) = match step {
+            CameraScriptStep::PanTo { pos, seconds } => (
+                (
+                    centered_camera_offset(&pos, from.1, &render_settings, &game_grid),
+                    from.1,
+                ),
+                seconds,
+            ),
+            CameraScriptStep::Zoom {
+                tile_scale,
+                seconds,
+            } => {
+                let tile_scale = tile_scale.max(MIN_TILE_SCALE).min(MAX_TILE_SCALE);
+                // Tile stride is proportional to tile scale, so scaling the offset by the same
+                // ratio keeps the centred tile in place.
+                let offset = clamp_camera_offset(
+                    from.0 * (tile_scale / from.1),
+                    tile_scale,
+                    &render_settings,
+                    &game_grid,
+                );
+                ((offset, tile_scale), seconds)
+            }
+            CameraScriptStep::Hold { seconds } => (from, seconds),
+        };
+
+        camera_script.current = Some(CameraScriptMove {
+            from,
+            to,
+            timer: Timer::from_seconds(seconds, false),
+        });
+    }
+
+    let mut finished = false;
+    if let Some(camera_move) = camera_script.current.as_mut() {
+        camera_move.timer.tick(time.delta());
+
+        let t = Easing::EaseInOut.apply(camera_move.timer.percent());
+        render_settings.camera_offset = camera_move.from.0.lerp(camera_move.to.0, t);
+        render_settings.tile_scale =
+            camera_move.from.1 + (camera_move.to.1 - camera_move.from.1) * t;
+
+        finished = camera_move.timer.finished();
+    }
+
+    if finished {
+        camera_script.current = None;
     }
 }
 
@@ -496,6 +1826,7 @@ impl ContainsPoint for Rect<f32> {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_mouse_interactions(
     mouse_input: Res<Input<MouseButton>>,
     mut q: Query<(
@@ -504,17 +1835,43 @@ fn handle_mouse_interactions(
         Option<&mut Hoverable>,
         Option<&mut Clickable>,
     )>,
+    animating_unit_query: Query<Entity, (With<ActionSequence>, With<Selectable>)>,
+    ui_interaction_query: Query<&Interaction>,
     windows: Res<Windows>,
+    game_rules: Res<GameRules>,
+    camera_script: Res<CameraScript>,
     mut last_click: ResMut<LastClick>,
+    mut queued_click: ResMut<QueuedClick>,
 ) {
     let window = windows.get_primary().unwrap();
 
+    last_click.this_frame = false;
+
     if let Some(mut position) = window.cursor_position() {
-        let clicked = mouse_input.just_pressed(MouseButton::Left);
+        // Clicks on a UI button belong to the button, not the grid underneath it.
+        let over_ui = ui_interaction_query
+            .iter()
+            .any(|interaction| *interaction != Interaction::None);
+        let mut clicked =
+            mouse_input.just_pressed(MouseButton::Left) && !over_ui && !camera_script.is_running();
 
         position.x -= window.width() / 2.;
         position.y -= window.height() / 2.;
 
+        // Clicks can't select or move anything while a unit is mid-action, they are either
+        // dropped or held back and replayed at their original position afterwards.
+        if animating_unit_query.iter().next().is_some() {
+            if clicked && game_rules.queue_clicks_during_animation {
+                queued_click.position = Some(position);
+            }
+            clicked = false;
+        } else if let Some(queued_position) = queued_click.position.take() {
+            if !clicked {
+                position = queued_position;
+                clicked = true;
+            }
+        }
+
         let mut click_handled = false;
 
         let mut highest_z_clicked: Option<(u32, Entity)> = None;
@@ -562,6 +1919,7 @@ fn handle_mouse_interactions(
 
         if clicked {
             last_click.was_handled = click_handled;
+            last_click.this_frame = true;
         }
     }
 }
@@ -630,240 +1988,2722 @@ fn handle_player_unit_selection_grid_highlights(
     }
 }
 
-fn render_grid_tiles(
+fn build_highlight_index(
     grid_highlight_query: Query<&GridHighlight>,
-    mut grid_tile_query: Query<(&mut TextureAtlasSprite, &GridPosition), With<GridTileTag>>,
+    mut highlight_index: ResMut<HighlightIndex>,
 ) {
-    let mut player_unit_selected = vec![];
-    let mut player_unit_movement = vec![];
-    let mut player_hover = vec![];
+    let highlight_index = &mut *highlight_index;
+    highlight_index.selected.clear();
+    highlight_index.movement.clear();
+    highlight_index.hover.clear();
+    highlight_index.objective.clear();
+    highlight_index.path_preview.clear();
+    highlight_index.path_preview_over_budget.clear();
 
     for grid_highlight in grid_highlight_query.iter() {
         use GridHighlightType::*;
-        match grid_highlight.highlight_type {
-            PlayerUnitSelected => player_unit_selected.push(grid_highlight.pos),
-            PlayerUnitMovement => player_unit_movement.push(grid_highlight.pos),
-            PlayerHover => player_hover.push(grid_highlight.pos),
+        let set = match grid_highlight.highlight_type {
+            PlayerUnitSelected => &mut highlight_index.selected,
+            PlayerUnitMovement => &mut highlight_index.movement,
+            PlayerHover | ClickFeedback => &mut highlight_index.hover,
+            Objective => &mut highlight_index.objective,
+            PathPreview => &mut highlight_index.path_preview,
+            PathPreviewOverBudget => &mut highlight_index.path_preview_over_budget,
         };
+        set.insert(grid_highlight.pos);
     }
+}
+
+fn render_grid_tiles(
+    highlight_index: Res<HighlightIndex>,
+    mut grid_tile_query: Query<(&mut TextureAtlasSprite, &GridPosition), With<GridTileTag>>,
+    map_ambient: Res<MapAmbient>,
+    time: Res<Time>,
+) {
+    let pulse = (time.seconds_since_startup() as f32 * 4.).sin() * 0.5 + 0.5;
 
     for (mut texture_atlas_sprite, grid_position) in grid_tile_query.iter_mut() {
-        if player_unit_selected.contains(&grid_position) {
+        if highlight_index.selected.contains(grid_position) {
             *texture_atlas_sprite = TextureAtlasSprite::new(0);
-        } else if player_unit_movement.contains(&grid_position) {
+        } else if highlight_index
+            .path_preview_over_budget
+            .contains(grid_position)
+        {
+            *texture_atlas_sprite = TextureAtlasSprite::new(7);
+        } else if highlight_index.path_preview.contains(grid_position) {
+            *texture_atlas_sprite = TextureAtlasSprite {
+                color: Color::rgb(0.4, 1., 0.4),
+                ..TextureAtlasSprite::new(3)
+            };
+        } else if highlight_index.movement.contains(grid_position) {
             *texture_atlas_sprite = TextureAtlasSprite::new(3);
-        } else if player_hover.contains(&grid_position) {
+        } else if highlight_index.hover.contains(grid_position) {
             *texture_atlas_sprite = TextureAtlasSprite::new(1);
+        } else if highlight_index.objective.contains(grid_position) {
+            *texture_atlas_sprite = TextureAtlasSprite {
+                color: Color::rgba(1., 0.84, 0., 0.5 + 0.5 * pulse),
+                ..TextureAtlasSprite::new(2)
+            };
         } else {
             *texture_atlas_sprite = TextureAtlasSprite::new(2);
         }
+
+        texture_atlas_sprite.color = texture_atlas_sprite.color * Vec4::from(map_ambient.tint);
+    }
+}
+
+/// Applies the map tint to units, after the animation systems have picked this frame's
+/// sprites, which always start out untinted.
+fn tint_units(
+    map_ambient: Res<MapAmbient>,
+    mut unit_query: Query<&mut TextureAtlasSprite, With<Selectable>>,
+) {
+    for mut texture_atlas_sprite in unit_query.iter_mut() {
+        texture_atlas_sprite.color = map_ambient.tint;
     }
 }
 
-fn handle_unit_selection(
+/// Resolves this frame's click to exactly one outcome, checked in this order:
+///
+/// 1. A click on a unit selects it, replacing any other selection. Clicking the unit that
+///    is already selected leaves it selected.
+/// 2. A click on a tile while a unit is selected orders a move there if it's a valid move,
+///    otherwise rejects the order, as out of range or as occupied, and deselects.
+/// 3. A click on a tile with nothing selected is acknowledged with a ripple.
+/// 4. A click that hit nothing deselects.
+#[allow(clippy::too_many_arguments)]
+fn handle_clicks(
     mut commands: Commands,
-    mut clickable_player_unit_query: Query<
-        (Entity, &Clickable, Option<&mut SelectedAnimation>),
+    mut clickable_unit_query: Query<
+        (
+            Entity,
+            &Clickable,
+            Option<&mut SelectedAnimation>,
+            Option<&HasActed>,
+        ),
         With<Selectable>,
     >,
-    mut selected_unit_query: Query<(Entity, Option<&mut IdleAnimation>), With<SelectedUnit>>,
+    selected_unit_query: Query<Entity, With<SelectedUnit>>,
+    unit_query: Query<(&GridPosition, Option<&MovementRange>), With<Selectable>>,
+    grid_tile_query: Query<(&Clickable, &GridPosition), With<GridTileTag>>,
+    game_grid: Res<GameGrid>,
     last_click: Res<LastClick>,
+    turn_state: Res<TurnState>,
+    mut reject: EventWriter<RejectEvent>,
 ) {
-    let mut remove_all_currently_selected = false;
-    for (entity, clickable, mut selected_animation) in clickable_player_unit_query.iter_mut() {
-        if clickable.clicked {
-            commands.entity(entity).insert(SelectedUnit {});
-            remove_all_currently_selected = true;
+    if !last_click.this_frame {
+        return;
+    }
+
+    let selected_unit = selected_unit_query.iter().next();
+
+    let clicked_unit = clickable_unit_query
+        .iter_mut()
+        .find(|(_, clickable, ..)| clickable.clicked);
+    if let Some((entity, _, selected_animation, has_acted)) = clicked_unit {
+        if !turn_state.can_select(entity) {
+            reject.send(RejectEvent {
+                reason: RejectReason::NotActiveUnit,
+            });
+            return;
+        }
+
+        if has_acted.is_some() {
+            reject.send(RejectEvent {
+                reason: RejectReason::AlreadyActed,
+            });
+            return;
+        }
 
-            if let Some(mut selected_animation) =
-                selected_animation.as_mut().map(|s| s.animation).flatten()
-            {
-                selected_animation.current_index = selected_animation.start_index;
+        for selected in selected_unit_query.iter() {
+            if selected != entity {
+                commands.entity(selected).remove::<SelectedUnit>();
+            }
+        }
+
+        if selected_unit != Some(entity) {
+            commands.entity(entity).insert(SelectedUnit);
+            if let Some(mut selected_animation) = selected_animation {
+                selected_animation.restart();
             }
-            break;
         }
+        return;
     }
 
-    if !last_click.was_handled {
-        remove_all_currently_selected = true;
+    let clicked_tile = grid_tile_query
+        .iter()
+        .find(|(clickable, _)| clickable.clicked)
+        .map(|(_, pos)| *pos);
+    if let Some(pos) = clicked_tile {
+        match selected_unit {
+            Some(entity) => {
+                let occupied = unit_query
+                    .iter()
+                    .map(|(pos, _)| *pos)
+                    .collect::<Vec<GridPosition>>();
+                let rejection = match unit_query.get(entity) {
+                    Ok((unit_pos, Some(movement_range))) => {
+                        let reachable = pathfinding::reachable_tiles(
+                            *unit_pos,
+                            movement_range.range,
+                            &game_grid,
+                        );
+                        let valid_moves =
+                            rules::valid_moves(*unit_pos, movement_range, &game_grid, &occupied);
+
+                        if !reachable.contains(&pos) {
+                            Some(RejectReason::OutOfRange)
+                        } else if !valid_moves.contains(&pos) {
+                            Some(RejectReason::TileOccupied)
+                        } else {
+                            None
+                        }
+                    }
+                    _ => Some(RejectReason::OutOfRange),
+                };
+
+                match rejection {
+                    None => {
+                        commands
+                            .entity(entity)
+                            .insert(ActionSequence::new(vec![ActionStep::Walk(pos)]))
+                            .insert(HasActed)
+                            .remove::<SelectedUnit>();
+                    }
+                    Some(reason) => {
+                        reject.send(RejectEvent { reason });
+                        commands.entity(entity).remove::<SelectedUnit>();
+                    }
+                }
+            }
+            None => {
+                commands
+                    .spawn()
+                    .insert(GridHighlight {
+                        pos,
+                        highlight_type: GridHighlightType::ClickFeedback,
+                    })
+                    .insert(ClickRipple {
+                        timer: Timer::from_seconds(0.15, false),
+                    });
+            }
+        }
+        return;
     }
 
-    if remove_all_currently_selected {
-        for (entity, idle_animation) in selected_unit_query.iter_mut() {
+    if !last_click.was_handled {
+        for entity in selected_unit_query.iter() {
             commands.entity(entity).remove::<SelectedUnit>();
         }
     }
 }
 
-fn handle_player_unit_selection_movement_highlights(
-    mut commands: Commands,
-    selected_unit_query: Query<&GridPosition, With<SelectedUnit>>,
-    grid_tile_query: Query<&GridPosition, With<GridTileTag>>,
-    grid_highlight_query: Query<(Entity, &GridHighlight)>,
-    player_unit_query: Query<(&GridPosition, &MovementRange)>,
+/// Shows every stat of the hovered unit, or the selected one, while Alt is held.
+fn update_stat_sheet(
+    keyboard_input: Res<Input<KeyCode>>,
+    unit_query: Query<
+        (
+            Entity,
+            &GridPosition,
+            &Hoverable,
+            Option<&UnitName>,
+            Option<&MovementRange>,
+            Option<&Facing>,
+            Option<&HasActed>,
+        ),
+        With<Selectable>,
+    >,
+    selected_unit_query: Query<Entity, With<SelectedUnit>>,
+    mut stat_sheet_query: Query<&mut Text, With<StatSheet>>,
 ) {
-    let mut selected_unit_movement_highlights = vec![];
-    for (entity, grid_highlight) in grid_highlight_query.iter() {
-        match grid_highlight.highlight_type {
-            GridHighlightType::PlayerUnitMovement => {
-                selected_unit_movement_highlights.push((entity, grid_highlight.pos));
-            }
-            _ => {}
-        }
-    }
+    let alt_held = keyboard_input.pressed(KeyCode::LAlt) || keyboard_input.pressed(KeyCode::RAlt);
 
-    if let Ok(selected_player_unit_pos) = selected_unit_query.single() {
-        let mut selected_unit_movement = None;
-        for (pos, movement_range) in player_unit_query.iter() {
-            if *pos == *selected_player_unit_pos {
-                selected_unit_movement = Some(movement_range);
+    let mut unit = None;
+    if alt_held {
+        let selected_unit = selected_unit_query.iter().next();
+        for u in unit_query.iter() {
+            let (entity, _, hoverable, ..) = u;
+            if hoverable.hovered {
+                unit = Some(u);
                 break;
+            } else if selected_unit.contains(&entity) {
+                unit = Some(u);
             }
         }
+    }
 
-        if let Some(selected_unit_movement) = selected_unit_movement {
-            let mut tiles_need_highlight = vec![];
-            for grid_position in grid_tile_query.iter() {
-                if grid_position.dist(&selected_player_unit_pos) <= selected_unit_movement.range
-                    && grid_position.dist(&selected_player_unit_pos) > 0
-                {
-                    tiles_need_highlight.push(*grid_position);
-                }
+    let stats = match unit {
+        Some((entity, pos, _, unit_name, movement_range, facing, has_acted)) => {
+            let mut lines = vec![];
+            if let Some(unit_name) = unit_name {
+                lines.push(unit_name.name.clone());
             }
-
-            for (entity, pos) in selected_unit_movement_highlights.iter() {
-                if !tiles_need_highlight.contains(pos) {
-                    commands.entity(*entity).despawn();
-                }
+            lines.push(format!("Id: {}", entity.id()));
+            lines.push(format!("Position: ({}, {})", pos.x, pos.y));
+            if let Some(movement_range) = movement_range {
+                lines.push(format!(
+                    "Movement: {}{}",
+                    movement_range.range,
+                    if movement_range.flying {
+                        " (flying)"
+                    } else {
+                        ""
+                    }
+                ));
             }
-
-            for pos in tiles_need_highlight {
-                if !selected_unit_movement_highlights
-                    .iter()
-                    .map(|(_, p)| *p)
-                    .collect::<Vec<GridPosition>>()
-                    .contains(&pos)
-                {
-                    commands.spawn().insert(GridHighlight {
-                        pos,
-                        highlight_type: GridHighlightType::PlayerUnitMovement,
-                    });
-                }
+            if let Some(facing) = facing {
+                lines.push(format!("Facing: {:?}", facing));
             }
+            lines.push(format!(
+                "Acted: {}",
+                if has_acted.is_some() { "yes" } else { "no" }
+            ));
+            lines.join("\n")
         }
-    } else {
-        for (entity, _) in selected_unit_movement_highlights {
-            commands.entity(entity).despawn();
+        None => String::new(),
+    };
+
+    for mut text in stat_sheet_query.iter_mut() {
+        if text.sections[0].value != stats {
+            text.sections[0].value = stats.clone();
         }
     }
 }
 
-fn handle_hover_grid_highlights(
+fn handle_end_turn_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    turn_state: Res<TurnState>,
+    mut end_turn: EventWriter<EndTurnEvent>,
+) {
+    if turn_state.turn == Turn::Player && keyboard_input.just_pressed(KeyCode::Return) {
+        end_turn.send(EndTurnEvent);
+    }
+}
+
+fn tick_turn_timer(
+    game_rules: Res<GameRules>,
+    turn_state: Res<TurnState>,
+    animating_unit_query: Query<Entity, With<ActionSequence>>,
+    mut turn_timer: ResMut<TurnTimer>,
+    mut end_turn: EventWriter<EndTurnEvent>,
+    time: Res<Time>,
+) {
+    if game_rules.turn_time_limit.is_none()
+        || turn_state.turn != Turn::Player
+        || turn_timer.remaining <= 0.
+    {
+        return;
+    }
+
+    // The clock only runs while the player can actually give orders.
+    if animating_unit_query.iter().next().is_some() {
+        return;
+    }
+
+    turn_timer.remaining -= time.delta_seconds();
+    if turn_timer.remaining <= 0. {
+        turn_timer.remaining = 0.;
+        end_turn.send(EndTurnEvent);
+    }
+}
+
+/// No faction other than the player has units yet, so their turns end straight away.
+fn pass_empty_turns(turn_state: Res<TurnState>, mut end_turn: EventWriter<EndTurnEvent>) {
+    if turn_state.turn != Turn::Player {
+        end_turn.send(EndTurnEvent);
+    }
+}
+
+/// The opening turn never goes through `advance_turn`, so this sends its start event
+/// for anything keyed on turn starts, like reinforcements scheduled for turn 1.
+fn announce_opening_turn(turn_state: Res<TurnState>, mut turn_start: EventWriter<TurnStartEvent>) {
+    turn_start.send(TurnStartEvent {
+        turn: turn_state.turn,
+        turn_number: turn_state.turn_number,
+    });
+}
+
+fn advance_turn(
     mut commands: Commands,
-    grid_tile_query: Query<(&GridPosition, &Hoverable), With<GridTileTag>>,
-    grid_highlight_query: Query<(Entity, &GridHighlight)>,
+    mut end_turn: EventReader<EndTurnEvent>,
+    mut turn_start: EventWriter<TurnStartEvent>,
+    mut turn_state: ResMut<TurnState>,
+    selected_unit_query: Query<Entity, With<SelectedUnit>>,
 ) {
-    let mut hover_highlights = vec![];
-    for (entity, grid_highlight) in grid_highlight_query.iter() {
-        match grid_highlight.highlight_type {
-            GridHighlightType::PlayerHover => {
-                hover_highlights.push((entity, grid_highlight.pos));
-            }
-            _ => {}
-        }
+    if end_turn.iter().count() == 0 {
+        return;
     }
 
-    let mut hovered_tiles = vec![];
-    for (pos, hoverable) in grid_tile_query.iter() {
-        if hoverable.hovered {
-            hovered_tiles.push(*pos);
-        }
+    for entity in selected_unit_query.iter() {
+        commands.entity(entity).remove::<SelectedUnit>();
     }
 
-    for (entity, pos) in hover_highlights.iter() {
-        if !hovered_tiles.contains(pos) {
-            commands.entity(*entity).despawn();
-        }
+    turn_state.turn = turn_state.turn.next();
+    if turn_state.turn == Turn::Player {
+        turn_state.turn_number += 1;
     }
 
-    for pos in hovered_tiles {
-        if !hover_highlights
-            .iter()
-            .map(|(_, p)| *p)
-            .collect::<Vec<GridPosition>>()
-            .contains(&pos)
-        {
-            commands.spawn().insert(GridHighlight {
-                pos,
-                highlight_type: GridHighlightType::PlayerHover,
-            });
+    turn_start.send(TurnStartEvent {
+        turn: turn_state.turn,
+        turn_number: turn_state.turn_number,
+    });
+}
+
+fn start_turn(
+    mut commands: Commands,
+    mut turn_start: EventReader<TurnStartEvent>,
+    game_rules: Res<GameRules>,
+    mut turn_timer: ResMut<TurnTimer>,
+    mut turn_flags: ResMut<TurnFlags>,
+    acted_query: Query<Entity, With<HasActed>>,
+) {
+    for ev in turn_start.iter() {
+        turn_flags.clear();
+
+        if ev.turn != Turn::Player {
+            continue;
+        }
+
+        for entity in acted_query.iter() {
+            commands.entity(entity).remove::<HasActed>();
         }
+
+        turn_timer.remaining = game_rules.turn_time_limit.unwrap_or(0.);
     }
 }
 
-fn handle_grid_clicks(
+fn spawn_reinforcements(
     mut commands: Commands,
-    grid_highlight_query: Query<&GridHighlight>,
-    grid_tile_query: Query<(&Clickable, &GridPosition), With<GridTileTag>>,
-    mut selected_unit_query: Query<
-        (Entity, &mut GridPosition),
-        (With<SelectedUnit>, Without<GridTileTag>),
-    >,
+    mut turn_start: EventReader<TurnStartEvent>,
+    reinforcements: Res<Reinforcements>,
+    sprite_sheets: Res<SpriteSheets>,
+    atlas_layouts: Res<AtlasLayouts>,
+    mut unit_name_registry: ResMut<UnitNameRegistry>,
+    unit_query: Query<&GridPosition, With<Selectable>>,
 ) {
-    if let Ok((entity, mut selected_player_unit_pos)) = selected_unit_query.single_mut() {
-        let movement_highlight_positions = grid_highlight_query
-            .iter()
-            .filter(|grid_highlight| {
-                grid_highlight.highlight_type == GridHighlightType::PlayerUnitMovement
-            })
-            .map(|grid_highlight| grid_highlight.pos)
-            .collect::<Vec<GridPosition>>();
+    for ev in turn_start.iter() {
+        if ev.turn != Turn::Player {
+            continue;
+        }
 
-        for (clickable, pos) in grid_tile_query.iter() {
-            if clickable.clicked && movement_highlight_positions.contains(pos) {
-                *selected_player_unit_pos = *pos;
-                commands.entity(entity).remove::<SelectedUnit>();
-                break;
-            } else if clickable.clicked {
-                commands.entity(entity).remove::<SelectedUnit>();
-                break;
+        for reinforcement in reinforcements.schedule.iter() {
+            if reinforcement.turn_number != ev.turn_number {
+                continue;
+            }
+
+            if unit_query.iter().any(|pos| *pos == reinforcement.pos) {
+                warn!(
+                    "reinforcement {} can't arrive at occupied tile {:?}",
+                    reinforcement.template, reinforcement.pos
+                );
+                continue;
+            }
+
+            let entity = match reinforcement.template.as_str() {
+                "Myrrh" => spawn_myrrh(
+                    &mut commands,
+                    &sprite_sheets,
+                    &atlas_layouts.myrrh,
+                    reinforcement.pos,
+                    MYRRH_MOVEMENT_RANGE,
+                    unit_name_registry.next_name("Myrrh"),
+                ),
+                template => {
+                    warn!("unknown reinforcement template {}", template);
+                    continue;
+                }
+            };
+
+            commands
+                .entity(entity)
+                .insert(ActionSequence::new(vec![ActionStep::Animate(
+                    AnimationRange::from_start_end(0, 7),
+                )]));
+
+            if reinforcements.arrive_exhausted {
+                commands.entity(entity).insert(HasActed);
             }
         }
     }
 }
 
-fn animate_idle(
-    mut idle_animation_query: Query<
-        (&mut TextureAtlasSprite, &mut IdleAnimation),
-        Without<SelectedUnit>,
-    >,
-    time: Res<Time>,
+fn update_turn_timer_text(
+    game_rules: Res<GameRules>,
+    turn_timer: Res<TurnTimer>,
+    mut turn_timer_text_query: Query<&mut Text, With<TurnTimerText>>,
 ) {
-    for (mut texture_atlas_sprite, mut idle_animation) in idle_animation_query.iter_mut() {
-        if idle_animation.timer.tick(time.delta()).just_finished() {
-            let should_loop = idle_animation.should_loop;
-            if let Some(animation) = idle_animation.animation.as_mut() {
-                *texture_atlas_sprite = TextureAtlasSprite::new(animation.current_index);
-                animation.advance(should_loop);
-            }
+    let countdown = match game_rules.turn_time_limit {
+        Some(_) => format!("{:.0}", turn_timer.remaining.ceil()),
+        None => String::new(),
+    };
+
+    for mut text in turn_timer_text_query.iter_mut() {
+        if text.sections[0].value != countdown {
+            text.sections[0].value = countdown.clone();
         }
     }
 }
 
-fn animate_selected(
-    mut selected_animation_query: Query<
-        (&mut TextureAtlasSprite, &mut SelectedAnimation),
-        With<SelectedUnit>,
-    >,
-    time: Res<Time>,
+fn spawn_unit_name_labels(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    unit_query: Query<(Entity, &UnitName), Added<UnitName>>,
 ) {
-    for (mut texture_atlas_sprite, mut selected_animation) in selected_animation_query.iter_mut() {
-        if selected_animation.timer.tick(time.delta()).just_finished() {
-            let should_loop = selected_animation.should_loop;
-            if let Some(animation) = selected_animation.animation.as_mut() {
-                *texture_atlas_sprite = TextureAtlasSprite::new(animation.current_index);
-                animation.advance(should_loop);
+    for (unit, unit_name) in unit_query.iter() {
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::with_section(
+                    unit_name.name.clone(),
+                    TextStyle {
+                        font: fonts.ui.clone(),
+                        font_size: 16.,
+                        color: Color::WHITE,
+                    },
+                    TextAlignment {
+                        vertical: VerticalAlign::Center,
+                        horizontal: HorizontalAlign::Center,
+                    },
+                ),
+                ..Default::default()
+            })
+            .insert(UnitNameLabel { unit });
+    }
+}
+
+fn spawn_roster_entries(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    roster_materials: Res<RosterMaterials>,
+    roster_panel_query: Query<Entity, With<RosterPanel>>,
+    unit_query: Query<(Entity, &UnitName), (Added<UnitName>, With<Selectable>)>,
+) {
+    let roster_panel = match roster_panel_query.single() {
+        Ok(roster_panel) => roster_panel,
+        Err(_) => return,
+    };
+
+    for (unit, unit_name) in unit_query.iter() {
+        commands.entity(roster_panel).with_children(|parent| {
+            parent
+                .spawn_bundle(ButtonBundle {
+                    style: Style {
+                        margin: Rect {
+                            bottom: Val::Px(4.),
+                            ..Default::default()
+                        },
+                        padding: Rect::all(Val::Px(4.)),
+                        ..Default::default()
+                    },
+                    material: roster_materials.entry.clone(),
+                    ..Default::default()
+                })
+                .insert(RosterEntry { unit })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(TextBundle {
+                            text: Text::with_section(
+                                unit_name.name.clone(),
+                                TextStyle {
+                                    font: fonts.ui.clone(),
+                                    font_size: 18.,
+                                    color: Color::WHITE,
+                                },
+                                TextAlignment::default(),
+                            ),
+                            ..Default::default()
+                        })
+                        .insert(RosterEntryText { unit });
+                });
+        });
+    }
+}
+
+/// Greys out units that have acted and drops entries for units that no longer exist.
+fn update_roster_entries(
+    mut commands: Commands,
+    entry_query: Query<(Entity, &RosterEntry)>,
+    mut entry_text_query: Query<(&RosterEntryText, &mut Text)>,
+    unit_query: Query<(&UnitName, Option<&HasActed>)>,
+) {
+    for (entity, entry) in entry_query.iter() {
+        if unit_query.get(entry.unit).is_err() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    for (entry_text, mut text) in entry_text_query.iter_mut() {
+        if let Ok((unit_name, has_acted)) = unit_query.get(entry_text.unit) {
+            let (value, color) = match has_acted {
+                Some(_) => (format!("{} (acted)", unit_name.name), Color::GRAY),
+                None => (unit_name.name.clone(), Color::WHITE),
+            };
+
+            let section = &mut text.sections[0];
+            if section.value != value {
+                section.value = value;
+                section.style.color = color;
+            }
+        }
+    }
+}
+
+/// Selects the unit behind a clicked roster entry and centres the camera on it.
+#[allow(clippy::too_many_arguments)]
+fn handle_roster_clicks(
+    mut commands: Commands,
+    entry_query: Query<(&Interaction, &RosterEntry), Changed<Interaction>>,
+    mut unit_query: Query<
+        (
+            &GridPosition,
+            Option<&mut SelectedAnimation>,
+            Option<&HasActed>,
+        ),
+        With<Selectable>,
+    >,
+    selected_unit_query: Query<Entity, With<SelectedUnit>>,
+    animating_unit_query: Query<Entity, (With<ActionSequence>, With<Selectable>)>,
+    turn_state: Res<TurnState>,
+    render_settings: Res<RenderSettings>,
+    game_grid: Res<GameGrid>,
+    camera_script: Res<CameraScript>,
+    mut camera_transition: ResMut<CameraTransition>,
+    mut reject: EventWriter<RejectEvent>,
+) {
+    if camera_script.is_running() {
+        return;
+    }
+
+    for (interaction, entry) in entry_query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        let (pos, selected_animation, has_acted) = match unit_query.get_mut(entry.unit) {
+            Ok(unit) => unit,
+            Err(_) => continue,
+        };
+
+        let offset = centered_camera_offset(
+            pos,
+            render_settings.tile_scale,
+            &render_settings,
+            &game_grid,
+        );
+        camera_transition.target = Some((offset, render_settings.tile_scale));
+
+        if animating_unit_query.iter().next().is_some() {
+            break;
+        }
+
+        if !turn_state.can_select(entry.unit) {
+            reject.send(RejectEvent {
+                reason: RejectReason::NotActiveUnit,
+            });
+            break;
+        }
+
+        if has_acted.is_some() {
+            reject.send(RejectEvent {
+                reason: RejectReason::AlreadyActed,
+            });
+            break;
+        }
+
+        for selected_unit in selected_unit_query.iter() {
+            if selected_unit != entry.unit {
+                commands.entity(selected_unit).remove::<SelectedUnit>();
+            }
+        }
+        commands.entity(entry.unit).insert(SelectedUnit);
+
+        if let Some(mut selected_animation) = selected_animation {
+            selected_animation.restart();
+        }
+        break;
+    }
+}
+
+fn toggle_unit_names(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut render_settings: ResMut<RenderSettings>,
+) {
+    if keyboard_input.just_pressed(KeyCode::N) {
+        render_settings.show_unit_names = !render_settings.show_unit_names;
+    }
+}
+
+fn update_unit_name_labels(
+    mut commands: Commands,
+    render_settings: Res<RenderSettings>,
+    mut label_query: Query<(Entity, &UnitNameLabel, &mut Transform, &mut Visible)>,
+    unit_query: Query<&Transform, (With<UnitName>, Without<UnitNameLabel>)>,
+) {
+    let label_height = render_settings.tile_size * render_settings.tile_scale / 2.;
+
+    for (entity, label, mut transform, mut visible) in label_query.iter_mut() {
+        match unit_query.get(label.unit) {
+            Ok(unit_transform) => {
+                transform.translation =
+                    unit_transform.translation + Vec3::new(0., label_height, 1.);
+                visible.is_visible = render_settings.show_unit_names;
+            }
+            Err(_) => {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+fn track_last_selected(
+    mut last_selected: ResMut<LastSelected>,
+    deselected: RemovedComponents<SelectedUnit>,
+    acted_query: Query<&HasActed>,
+    turn_state: Res<TurnState>,
+) {
+    if turn_state.is_changed() {
+        last_selected.0 = None;
+        return;
+    }
+
+    for entity in deselected.iter() {
+        if acted_query.get(entity).is_err() {
+            last_selected.0 = Some(entity);
+        }
+    }
+}
+
+/// Reselects the last deselected unit on R. Like clicks, recalls are ignored while a
+/// unit is mid-action or a camera script runs, and a unit that can't be selected right
+/// now stays remembered.
+#[allow(clippy::too_many_arguments)]
+fn recall_last_selected(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut last_selected: ResMut<LastSelected>,
+    turn_state: Res<TurnState>,
+    camera_script: Res<CameraScript>,
+    selected_unit_query: Query<Entity, With<SelectedUnit>>,
+    animating_unit_query: Query<Entity, (With<ActionSequence>, With<Selectable>)>,
+    mut recallable_query: Query<
+        Option<&mut SelectedAnimation>,
+        (With<Selectable>, Without<HasActed>),
+    >,
+) {
+    if !keyboard_input.just_pressed(KeyCode::R)
+        || selected_unit_query.iter().next().is_some()
+        || animating_unit_query.iter().next().is_some()
+        || camera_script.is_running()
+    {
+        return;
+    }
+
+    let entity = match last_selected.0 {
+        Some(entity) => entity,
+        None => return,
+    };
+
+    match recallable_query.get_mut(entity) {
+        Ok(selected_animation) => {
+            if !turn_state.can_select(entity) {
+                return;
+            }
+
+            last_selected.0 = None;
+            commands.entity(entity).insert(SelectedUnit);
+
+            if let Some(mut selected_animation) = selected_animation {
+                selected_animation.restart();
+            }
+        }
+        Err(_) => {
+            last_selected.0 = None;
+        }
+    }
+}
+
+fn handle_player_unit_selection_movement_highlights(
+    mut commands: Commands,
+    selected_unit_query: Query<&GridPosition, With<SelectedUnit>>,
+    grid_highlight_query: Query<(Entity, &GridHighlight)>,
+    player_unit_query: Query<(&GridPosition, &MovementRange)>,
+    unit_query: Query<&GridPosition, With<Selectable>>,
+    game_grid: Res<GameGrid>,
+) {
+    let mut selected_unit_movement_highlights = vec![];
+    for (entity, grid_highlight) in grid_highlight_query.iter() {
+        match grid_highlight.highlight_type {
+            GridHighlightType::PlayerUnitMovement => {
+                selected_unit_movement_highlights.push((entity, grid_highlight.pos));
+            }
+            _ => {}
+        }
+    }
+
+    if let Ok(selected_player_unit_pos) = selected_unit_query.single() {
+        let mut selected_unit_movement = None;
+        for (pos, movement_range) in player_unit_query.iter() {
+            if *pos == *selected_player_unit_pos {
+                selected_unit_movement = Some(movement_range);
+                break;
+            }
+        }
+
+        if let Some(selected_unit_movement) = selected_unit_movement {
+            let occupied = unit_query.iter().cloned().collect::<Vec<GridPosition>>();
+            let tiles_need_highlight = rules::valid_moves(
+                *selected_player_unit_pos,
+                selected_unit_movement,
+                &game_grid,
+                &occupied,
+            );
+
+            for (entity, pos) in selected_unit_movement_highlights.iter() {
+                if !tiles_need_highlight.contains(pos) {
+                    commands.entity(*entity).despawn();
+                }
+            }
+
+            for pos in tiles_need_highlight {
+                if !selected_unit_movement_highlights
+                    .iter()
+                    .map(|(_, p)| *p)
+                    .collect::<Vec<GridPosition>>()
+                    .contains(&pos)
+                {
+                    commands.spawn().insert(GridHighlight {
+                        pos,
+                        highlight_type: GridHighlightType::PlayerUnitMovement,
+                    });
+                }
+            }
+        }
+    } else {
+        for (entity, _) in selected_unit_movement_highlights {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Previews the path the selected unit would take to the hovered tile, marking the steps
+/// past its movement range as over budget.
+fn handle_path_preview_highlights(
+    mut commands: Commands,
+    selected_unit_query: Query<(&GridPosition, &MovementRange), With<SelectedUnit>>,
+    grid_tile_query: Query<(&GridPosition, &Hoverable), With<GridTileTag>>,
+    grid_highlight_query: Query<(Entity, &GridHighlight)>,
+    unit_query: Query<&GridPosition, With<Selectable>>,
+    game_grid: Res<GameGrid>,
+) {
+    let mut path_highlights = vec![];
+    if let Ok((unit_pos, movement_range)) = selected_unit_query.single() {
+        let hovered_tile = grid_tile_query
+            .iter()
+            .find(|(_, hoverable)| hoverable.hovered)
+            .map(|(pos, _)| *pos);
+        let occupied = unit_query.iter().cloned().collect::<Vec<GridPosition>>();
+
+        if let Some(hovered_tile) = hovered_tile.filter(|pos| !occupied.contains(pos)) {
+            // No step can be further than the grid's diagonal, so this reaches every tile.
+            let max_steps = (game_grid.width + game_grid.height) as u32;
+            let reachability =
+                pathfinding::reachability_tree(*unit_pos, max_steps, &game_grid, &[]);
+
+            if let Some(path) = reachability.path_to(hovered_tile) {
+                for (step, pos) in path.into_iter().enumerate() {
+                    let cost = step as u32 + 1;
+                    let highlight_type = if cost <= movement_range.range {
+                        GridHighlightType::PathPreview
+                    } else {
+                        GridHighlightType::PathPreviewOverBudget
+                    };
+                    path_highlights.push((pos, highlight_type));
+                }
+            }
+        }
+    }
+
+    let mut existing_highlights = vec![];
+    for (entity, grid_highlight) in grid_highlight_query.iter() {
+        match grid_highlight.highlight_type {
+            GridHighlightType::PathPreview | GridHighlightType::PathPreviewOverBudget => {
+                let highlight = (grid_highlight.pos, grid_highlight.highlight_type);
+                if path_highlights.contains(&highlight) {
+                    existing_highlights.push(highlight);
+                } else {
+                    commands.entity(entity).despawn();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for (pos, highlight_type) in path_highlights {
+        if !existing_highlights.contains(&(pos, highlight_type)) {
+            commands.spawn().insert(GridHighlight {
+                pos,
+                highlight_type,
+            });
+        }
+    }
+}
+
+fn handle_hover_grid_highlights(
+    mut commands: Commands,
+    grid_tile_query: Query<(&GridPosition, &Hoverable), With<GridTileTag>>,
+    grid_highlight_query: Query<(Entity, &GridHighlight)>,
+) {
+    let mut hover_highlights = vec![];
+    for (entity, grid_highlight) in grid_highlight_query.iter() {
+        match grid_highlight.highlight_type {
+            GridHighlightType::PlayerHover => {
+                hover_highlights.push((entity, grid_highlight.pos));
+            }
+            _ => {}
+        }
+    }
+
+    let mut hovered_tiles = vec![];
+    for (pos, hoverable) in grid_tile_query.iter() {
+        if hoverable.hovered {
+            hovered_tiles.push(*pos);
+        }
+    }
+
+    for (entity, pos) in hover_highlights.iter() {
+        if !hovered_tiles.contains(pos) {
+            commands.entity(*entity).despawn();
+        }
+    }
+
+    for pos in hovered_tiles {
+        if !hover_highlights
+            .iter()
+            .map(|(_, p)| *p)
+            .collect::<Vec<GridPosition>>()
+            .contains(&pos)
+        {
+            commands.spawn().insert(GridHighlight {
+                pos,
+                highlight_type: GridHighlightType::PlayerHover,
+            });
+        }
+    }
+}
+
+fn update_objectives(
+    mut scenario: ResMut<Scenario>,
+    unit_query: Query<&GridPosition, (With<Selectable>, Without<ActionSequence>)>,
+) {
+    for scenario_objective in scenario.objectives.iter_mut() {
+        if scenario_objective.completed {
+            continue;
+        }
+
+        match scenario_objective.objective {
+            Objective::ReachTile(target) => {
+                if unit_query.iter().any(|pos| *pos == target) {
+                    scenario_objective.completed = true;
+                }
+            }
+        }
+    }
+}
+
+fn handle_objective_grid_highlights(
+    mut commands: Commands,
+    scenario: Res<Scenario>,
+    grid_highlight_query: Query<(Entity, &GridHighlight)>,
+) {
+    let mut objective_tiles = scenario
+        .objectives
+        .iter()
+        .filter(|o| !o.completed)
+        .map(|o| match o.objective {
+            Objective::ReachTile(pos) => pos,
+        })
+        .collect::<Vec<GridPosition>>();
+    objective_tiles.sort_by_key(GridPosition::row_major_key);
+
+    let mut objective_highlights = vec![];
+    for (entity, grid_highlight) in grid_highlight_query.iter() {
+        if grid_highlight.highlight_type == GridHighlightType::Objective {
+            if objective_tiles.contains(&grid_highlight.pos) {
+                objective_highlights.push(grid_highlight.pos);
+            } else {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+
+    for pos in objective_tiles {
+        if !objective_highlights.contains(&pos) {
+            commands.spawn().insert(GridHighlight {
+                pos,
+                highlight_type: GridHighlightType::Objective,
+            });
+        }
+    }
+}
+
+fn expire_click_ripples(
+    mut commands: Commands,
+    mut q: Query<(Entity, &mut ClickRipple)>,
+    time: Res<Time>,
+) {
+    for (entity, mut click_ripple) in q.iter_mut() {
+        if click_ripple.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn show_reject_toasts(
+    mut commands: Commands,
+    mut reject: EventReader<RejectEvent>,
+    windows: Res<Windows>,
+    fonts: Res<Fonts>,
+) {
+    let cursor = windows
+        .get_primary()
+        .map(|window| window.cursor_position())
+        .flatten()
+        .unwrap_or_default();
+
+    for ev in reject.iter() {
+        commands
+            .spawn_bundle(TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        left: Val::Px(cursor.x + 12.),
+                        bottom: Val::Px(cursor.y + 12.),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                text: Text::with_section(
+                    ev.reason.message(),
+                    TextStyle {
+                        font: fonts.ui.clone(),
+                        font_size: 18.,
+                        color: Color::rgb(0.85, 0.34, 0.39),
+                    },
+                    TextAlignment::default(),
+                ),
+                ..Default::default()
+            })
+            .insert(Toast {
+                timer: Timer::from_seconds(1., false),
+            });
+    }
+}
+
+fn expire_toasts(mut commands: Commands, mut q: Query<(Entity, &mut Toast)>, time: Res<Time>) {
+    for (entity, mut toast) in q.iter_mut() {
+        if toast.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn process_action_sequence(
+    mut commands: Commands,
+    mut q: Query<(
+        Entity,
+        &mut ActionSequence,
+        &mut GridPosition,
+        Option<&mut Facing>,
+    )>,
+    mut animation_finished: EventReader<AnimationFinished>,
+    sprite_sheets: Res<SpriteSheets>,
+) {
+    let finished = animation_finished
+        .iter()
+        .map(|ev| ev.entity)
+        .collect::<Vec<Entity>>();
+
+    for (entity, mut action_sequence, mut pos, facing) in q.iter_mut() {
+        if action_sequence.in_progress {
+            if !finished.contains(&entity) {
+                continue;
+            }
+            action_sequence.in_progress = false;
+        }
+
+        match action_sequence.steps.pop_front() {
+            Some(ActionStep::Walk(to)) => {
+                if let Some(mut facing) = facing {
+                    let dx = to.x as i32 - pos.x as i32;
+                    let dy = to.y as i32 - pos.y as i32;
+                    if let Some(new_facing) = Facing::from_delta(dx, dy) {
+                        *facing = new_facing;
+                    }
+                }
+
+                const WALK_SECONDS_PER_TILE: f32 = 0.1;
+                commands.entity(entity).insert(MovementTween {
+                    from: *pos,
+                    timer: Timer::from_seconds(WALK_SECONDS_PER_TILE * pos.dist(&to) as f32, false),
+                    easing: Easing::EaseInOut,
+                });
+                commands
+                    .spawn_bundle(SpriteSheetBundle {
+                        texture_atlas: sprite_sheets.grid.clone(),
+                        sprite: TextureAtlasSprite::new(0),
+                        ..Default::default()
+                    })
+                    .insert(DestinationMarker {
+                        unit: entity,
+                        pos: to,
+                        timer: Timer::from_seconds(0.6, true),
+                    });
+                *pos = to;
+                action_sequence.in_progress = true;
+            }
+            Some(ActionStep::Animate(animation)) => {
+                commands.entity(entity).insert(ActionAnimation {
+                    animation,
+                    timer: Timer::from_seconds(0.1, true),
+                });
+                action_sequence.in_progress = true;
+            }
+            None => {
+                commands.entity(entity).remove::<ActionSequence>();
+            }
+        }
+    }
+}
+
+fn animate_movement_tween(
+    mut commands: Commands,
+    mut q: Query<(Entity, &mut MovementTween)>,
+    mut animation_finished: EventWriter<AnimationFinished>,
+    time: Res<Time>,
+) {
+    for (entity, mut movement_tween) in q.iter_mut() {
+        if movement_tween.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<MovementTween>();
+            animation_finished.send(AnimationFinished { entity });
+        }
+    }
+}
+
+fn animate_destination_markers(
+    mut commands: Commands,
+    render_settings: Res<RenderSettings>,
+    mut marker_query: Query<(
+        Entity,
+        &mut DestinationMarker,
+        &mut Transform,
+        &mut TextureAtlasSprite,
+    )>,
+    walking_unit_query: Query<&MovementTween>,
+    time: Res<Time>,
+) {
+    let tile_scale =
+        render_settings.tile_size / render_settings.tile_texture_size * render_settings.tile_scale;
+
+    for (entity, mut marker, mut transform, mut sprite) in marker_query.iter_mut() {
+        if walking_unit_query.get(marker.unit).is_err() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        marker.timer.tick(time.delta());
+        let pulse = (marker.timer.percent() * std::f32::consts::PI * 2.).sin() * 0.5 + 0.5;
+
+        let center = tile_to_world(&marker.pos, &render_settings);
+        // Above movement highlights and tiles, below units.
+        transform.translation = Vec3::new(center.x, center.y, 8.);
+        transform.scale = Vec3::new(tile_scale, tile_scale, 1.) * (0.6 + 0.2 * pulse);
+        sprite.color = Color::rgba(1., 0.84, 0., 0.6 + 0.4 * pulse);
+    }
+}
+
+fn update_facing_animations(
+    mut q: Query<(&Facing, &FacingAnimations, &mut IdleAnimation), Changed<Facing>>,
+) {
+    for (facing, facing_animations, mut idle_animation) in q.iter_mut() {
+        if let Some(animation) = facing_animations.idle.get(*facing) {
+            idle_animation.animation = Some(animation);
+        }
+    }
+}
+
+fn animate_idle(
+    mut idle_animation_query: Query<
+        (&mut TextureAtlasSprite, &mut IdleAnimation, &mut Transform),
+        (Without<SelectedUnit>, Without<ActionAnimation>),
+    >,
+    time: Res<Time>,
+) {
+    for (mut texture_atlas_sprite, mut idle_animation, mut transform) in
+        idle_animation_query.iter_mut()
+    {
+        if idle_animation.timer.tick(time.delta()).just_finished() {
+            let should_loop = idle_animation.should_loop;
+            if let Some(animation) = idle_animation.animation.as_mut() {
+                *texture_atlas_sprite = TextureAtlasSprite::new(animation.current_index);
+                animation.advance(should_loop);
+            }
+        }
+
+        // render_grid_objects rewrites the scale every frame, so this never accumulates.
+        if idle_animation.interpolate {
+            let squash = idle_squash(idle_animation.timer.percent());
+            transform.scale.x *= squash.x;
+            transform.scale.y *= squash.y;
+        }
+    }
+}
+
+fn animate_selected(
+    mut selected_animation_query: Query<
+        (&mut TextureAtlasSprite, &mut SelectedAnimation),
+        (With<SelectedUnit>, Without<ActionAnimation>),
+    >,
+    time: Res<Time>,
+) {
+    for (mut texture_atlas_sprite, mut selected_animation) in selected_animation_query.iter_mut() {
+        if selected_animation.timer.tick(time.delta()).just_finished() {
+            if selected_animation.playing_chained {
+                if let Some(chained) = selected_animation.chained.as_mut() {
+                    *texture_atlas_sprite = TextureAtlasSprite::new(chained.current_index);
+                    chained.advance(true);
+                }
+                continue;
+            }
+
+            let should_loop = selected_animation.should_loop;
+            let has_chained = selected_animation.chained.is_some();
+            let mut finished = false;
+            if let Some(animation) = selected_animation.animation.as_mut() {
+                *texture_atlas_sprite = TextureAtlasSprite::new(animation.current_index);
+                finished = !should_loop && animation.current_index == animation.end_index;
+                animation.advance(should_loop);
+            }
+
+            if finished && has_chained {
+                selected_animation.playing_chained = true;
+            }
+        }
+    }
+}
+
+fn animate_action(
+    mut commands: Commands,
+    mut action_animation_query: Query<(Entity, &mut TextureAtlasSprite, &mut ActionAnimation)>,
+    mut animation_finished: EventWriter<AnimationFinished>,
+    time: Res<Time>,
+) {
+    for (entity, mut texture_atlas_sprite, mut action_animation) in
+        action_animation_query.iter_mut()
+    {
+        if action_animation.timer.tick(time.delta()).just_finished() {
+            let animation = &mut action_animation.animation;
+            *texture_atlas_sprite = TextureAtlasSprite::new(animation.current_index);
+
+            if animation.current_index == animation.end_index {
+                commands.entity(entity).remove::<ActionAnimation>();
+                animation_finished.send(AnimationFinished { entity });
+            } else {
+                animation.advance(false);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::app::Events;
+
+    fn test_grid(width: usize, height: usize) -> GameGrid {
+        GameGrid {
+            width,
+            height,
+            decorative_tiles: vec![],
+        }
+    }
+
+    fn test_render_settings() -> RenderSettings {
+        RenderSettings {
+            tile_size: 64.,
+            tile_scale: 2.,
+            tile_texture_size: 32.,
+            tile_border: 2.,
+            camera_offset: Vec2::ZERO,
+            tile_gap: 0.,
+            cull_offscreen_tiles: true,
+            show_unit_names: true,
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Required resources were not inserted during startup: SpriteSheets")]
+    fn resource_check_names_missing_resource() {
+        let mut world = World::default();
+        world.insert_resource(test_grid(4, 4));
+        world.insert_resource(test_render_settings());
+        world.insert_resource(TurnState::default());
+        world.insert_resource(LastClick::default());
+        world.insert_resource(Fonts {
+            ui: Handle::default(),
+        });
+        world.insert_resource(GameRules::default());
+
+        SystemStage::single(check_required_resources.system()).run(&mut world);
+    }
+
+    #[test]
+    fn facing_from_delta() {
+        assert_eq!(Facing::from_delta(1, 1), Some(Facing::NorthEast));
+        assert_eq!(Facing::from_delta(-2, -2), Some(Facing::SouthWest));
+        assert_eq!(Facing::from_delta(0, 3), Some(Facing::North));
+        assert_eq!(Facing::from_delta(3, 1), Some(Facing::East));
+        assert_eq!(Facing::from_delta(0, 0), None);
+    }
+
+    #[test]
+    fn missing_diagonal_falls_back_to_cardinal() {
+        let mut table = FacingAnimationTable::default();
+        table.frames[Facing::North as usize] = Some(AnimationRange::from_start_end(4, 5));
+
+        let animation = table.get(Facing::NorthEast).unwrap();
+        assert_eq!(animation.start_index, 4);
+
+        table.frames[Facing::East as usize] = Some(AnimationRange::from_start_end(2, 3));
+        let animation = table.get(Facing::NorthEast).unwrap();
+        assert_eq!(animation.start_index, 2);
+
+        assert!(table.get(Facing::South).is_none());
+    }
+
+    #[test]
+    fn myrrh_has_idle_frames_for_every_facing() {
+        let table = myrrh_idle_facing_table();
+        for facing in Facing::ALL.iter() {
+            assert!(
+                table.get(*facing).is_some(),
+                "{:?} has no idle frames",
+                facing
+            );
+        }
+    }
+
+    #[test]
+    fn initiative_mode_only_selects_active_unit() {
+        let active = Entity::new(1);
+        let other = Entity::new(2);
+
+        let turn_state = TurnState {
+            mode: TurnMode::Initiative,
+            active_unit: Some(active),
+            ..Default::default()
+        };
+        assert!(turn_state.can_select(active));
+        assert!(!turn_state.can_select(other));
+
+        let no_active_unit = TurnState {
+            mode: TurnMode::Initiative,
+            ..Default::default()
+        };
+        assert!(!no_active_unit.can_select(active));
+
+        let whole_faction = TurnState::default();
+        assert!(whole_faction.can_select(active));
+        assert!(whole_faction.can_select(other));
+    }
+
+    #[test]
+    fn tile_stride_overlaps_one_border() {
+        let stride = |tile_size: f32,
+                      tile_scale: f32,
+                      tile_texture_size: f32,
+                      tile_border: f32,
+                      tile_gap: f32| {
+            RenderSettings {
+                tile_size,
+                tile_scale,
+                tile_texture_size,
+                tile_border,
+                tile_gap,
+                ..test_render_settings()
+            }
+            .tile_stride()
+        };
+
+        // Borderless art tiles exactly `tile_size * tile_scale` apart.
+        assert_eq!(stride(64., 2., 32., 0., 0.), 128.);
+        assert_eq!(stride(48., 1., 16., 0., 0.), 48.);
+        // The 32 texel grid art with its 2 texel border overlaps by 1/16th of a tile.
+        assert_eq!(stride(64., 2., 32., 2., 0.), 120.);
+        assert_eq!(stride(32., 3., 32., 2., 0.), 90.);
+        // 16 texel art with the same border overlaps by 1/8th.
+        assert_eq!(stride(48., 1., 16., 2., 0.), 42.);
+        // Gaps are in unscaled pixels and added on top.
+        assert_eq!(stride(64., 2., 32., 2., 1.), 122.);
+    }
+
+    #[test]
+    fn atlas_layout_builds_one_frame_per_cell() {
+        let atlas_layouts = AtlasLayouts::default();
+        for layout in [&atlas_layouts.grid, &atlas_layouts.myrrh].iter() {
+            let atlas = layout.build(Handle::default());
+            assert_eq!(atlas.len(), layout.columns * layout.rows, "{}", layout.path);
+        }
+
+        let layout = AtlasLayout {
+            path: "textures/wide.png",
+            tile_width: 16,
+            tile_height: 24,
+            columns: 5,
+            rows: 2,
+        };
+        assert_eq!(layout.build(Handle::default()).len(), 10);
+        assert_eq!(layout.expected_size(), (80, 48));
+    }
+
+    #[test]
+    fn turn_flags_clear_when_turn_advances() {
+        let mut world = World::default();
+        world.insert_resource(Events::<EndTurnEvent>::default());
+        world.insert_resource(Events::<TurnStartEvent>::default());
+        world.insert_resource(TurnState {
+            turn_number: 1,
+            ..Default::default()
+        });
+        world.insert_resource(GameRules::default());
+        world.insert_resource(TurnTimer { remaining: 0. });
+        let mut turn_flags = TurnFlags::default();
+        turn_flags.set("attacked");
+        world.insert_resource(turn_flags);
+
+        let mut stage = SystemStage::single_threaded()
+            .with_system(advance_turn.system().label("advance_turn"))
+            .with_system(start_turn.system().after("advance_turn"));
+
+        stage.run(&mut world);
+        assert!(world
+            .get_resource::<TurnFlags>()
+            .unwrap()
+            .is_set("attacked"));
+
+        world
+            .get_resource_mut::<Events<EndTurnEvent>>()
+            .unwrap()
+            .send(EndTurnEvent);
+        stage.run(&mut world);
+
+        assert_eq!(world.get_resource::<TurnState>().unwrap().turn, Turn::Enemy);
+        assert!(!world
+            .get_resource::<TurnFlags>()
+            .unwrap()
+            .is_set("attacked"));
+    }
+
+    #[test]
+    fn reinforcements_arrive_on_their_turn() {
+        let mut world = World::default();
+        world.insert_resource(Events::<EndTurnEvent>::default());
+        world.insert_resource(Events::<TurnStartEvent>::default());
+        world.insert_resource(TurnState {
+            turn: Turn::Player,
+            turn_number: 1,
+            ..Default::default()
+        });
+        world.insert_resource(Reinforcements {
+            schedule: vec![
+                Reinforcement {
+                    turn_number: 1,
+                    template: "Myrrh".to_string(),
+                    pos: GridPosition { x: 0, y: 0 },
+                },
+                Reinforcement {
+                    turn_number: 3,
+                    template: "Myrrh".to_string(),
+                    pos: GridPosition { x: 1, y: 1 },
+                },
+            ],
+            arrive_exhausted: false,
+        });
+        world.insert_resource(SpriteSheets {
+            grid: Handle::default(),
+            myrrh: Handle::default(),
+        });
+        world.insert_resource(AtlasLayouts::default());
+        world.insert_resource(UnitNameRegistry::default());
+
+        SystemStage::single(announce_opening_turn.system()).run(&mut world);
+        let mut stage = SystemStage::single_threaded()
+            .with_system(advance_turn.system().label("advance_turn"))
+            .with_system(spawn_reinforcements.system().after("advance_turn"));
+
+        let unit_positions = |world: &mut World| {
+            let mut positions = world
+                .query_filtered::<&GridPosition, With<Selectable>>()
+                .iter(world)
+                .copied()
+                .collect::<Vec<GridPosition>>();
+            positions.sort_by_key(|pos| (pos.y, pos.x));
+            positions
+        };
+
+        stage.run(&mut world);
+        assert_eq!(
+            unit_positions(&mut world),
+            vec![GridPosition { x: 0, y: 0 }]
+        );
+
+        // Player, Enemy and Neutral each end their turn twice to reach turn 3.
+        for _ in 0..6 {
+            assert_eq!(unit_positions(&mut world).len(), 1);
+            world
+                .get_resource_mut::<Events<EndTurnEvent>>()
+                .unwrap()
+                .send(EndTurnEvent);
+            stage.run(&mut world);
+        }
+
+        let turn_state = world.get_resource::<TurnState>().unwrap();
+        assert_eq!((turn_state.turn, turn_state.turn_number), (Turn::Player, 3));
+        assert_eq!(
+            unit_positions(&mut world),
+            vec![GridPosition { x: 0, y: 0 }, GridPosition { x: 1, y: 1 }]
+        );
+    }
+
+    /// A world holding everything `handle_clicks` reads, with a click landing this frame.
+    fn click_world() -> World {
+        let mut world = World::default();
+        world.insert_resource(test_grid(8, 8));
+        world.insert_resource(LastClick {
+            was_handled: true,
+            this_frame: true,
+        });
+        world.insert_resource(TurnState::default());
+        world.insert_resource(Events::<RejectEvent>::default());
+        world
+    }
+
+    #[test]
+    fn clicking_selected_unit_keeps_it_selected() {
+        let mut world = click_world();
+        let unit = world
+            .spawn()
+            .insert(Clickable { clicked: true })
+            .insert(Selectable {})
+            .insert(SelectedUnit)
+            .insert(GridPosition { x: 2, y: 2 })
+            .id();
+
+        SystemStage::single(handle_clicks.system()).run(&mut world);
+
+        assert!(world.get::<SelectedUnit>(unit).is_some());
+    }
+
+    #[test]
+    fn initiative_click_on_other_unit_is_rejected() {
+        let mut world = click_world();
+        let active = world
+            .spawn()
+            .insert(Clickable::default())
+            .insert(Selectable {})
+            .insert(GridPosition { x: 1, y: 1 })
+            .id();
+        let other = world
+            .spawn()
+            .insert(Clickable { clicked: true })
+            .insert(Selectable {})
+            .insert(GridPosition { x: 2, y: 2 })
+            .id();
+        world.insert_resource(TurnState {
+            mode: TurnMode::Initiative,
+            active_unit: Some(active),
+            ..Default::default()
+        });
+
+        SystemStage::single(handle_clicks.system()).run(&mut world);
+
+        assert_eq!(sent_rejections(&world), vec![RejectReason::NotActiveUnit]);
+        assert!(world.get::<SelectedUnit>(other).is_none());
+        assert!(world.get::<SelectedUnit>(active).is_none());
+    }
+
+    /// Reasons of every rejection sent so far.
+    fn sent_rejections(world: &World) -> Vec<RejectReason> {
+        let events = world.get_resource::<Events<RejectEvent>>().unwrap();
+        events
+            .get_reader()
+            .iter(events)
+            .map(|ev| ev.reason)
+            .collect()
+    }
+
+    /// Spawns a selected unit at (2, 2) with range 3 and a blocker next to it, then
+    /// clicks `tile`. Returns the world and the selected unit.
+    fn click_tile_with_unit_selected(tile: GridPosition) -> (World, Entity) {
+        let mut world = click_world();
+        let unit = world
+            .spawn()
+            .insert(Clickable::default())
+            .insert(Selectable {})
+            .insert(SelectedUnit)
+            .insert(GridPosition { x: 2, y: 2 })
+            .insert(MovementRange {
+                range: 3,
+                flying: false,
+            })
+            .id();
+        world
+            .spawn()
+            .insert(Clickable::default())
+            .insert(Selectable {})
+            .insert(GridPosition { x: 3, y: 2 });
+        world
+            .spawn()
+            .insert(Clickable { clicked: true })
+            .insert(GridTileTag)
+            .insert(tile);
+
+        SystemStage::single(handle_clicks.system()).run(&mut world);
+
+        (world, unit)
+    }
+
+    #[test]
+    fn move_onto_occupied_tile_is_rejected_as_occupied() {
+        let (world, unit) = click_tile_with_unit_selected(GridPosition { x: 3, y: 2 });
+        assert_eq!(sent_rejections(&world), vec![RejectReason::TileOccupied]);
+        assert!(world.get::<SelectedUnit>(unit).is_none());
+        assert!(world.get::<ActionSequence>(unit).is_none());
+    }
+
+    #[test]
+    fn move_past_range_is_rejected_as_out_of_range() {
+        let (world, unit) = click_tile_with_unit_selected(GridPosition { x: 7, y: 7 });
+        assert_eq!(sent_rejections(&world), vec![RejectReason::OutOfRange]);
+        assert!(world.get::<SelectedUnit>(unit).is_none());
+        assert!(world.get::<ActionSequence>(unit).is_none());
+    }
+
+    #[test]
+    fn move_in_range_is_ordered() {
+        let (world, unit) = click_tile_with_unit_selected(GridPosition { x: 2, y: 4 });
+        assert!(sent_rejections(&world).is_empty());
+        assert!(world.get::<ActionSequence>(unit).is_some());
+        assert!(world.get::<HasActed>(unit).is_some());
+    }
+
+    #[test]
+    fn grid_index_round_trips() {
+        let grid = test_grid(7, 5);
+        for y in 0..5 {
+            for x in 0..7 {
+                let pos = GridPosition { x, y };
+                assert_eq!(GridPosition::from_index(pos.to_index(&grid), &grid), pos);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "(7, 0) is outside the grid")]
+    fn grid_index_rejects_out_of_bounds() {
+        GridPosition { x: 7, y: 0 }.to_index(&test_grid(7, 5));
+    }
+
+    /// The z-levels `render_grid_objects` placed non-unit objects on `positions` at
+    /// before `HighlightIndex`, collected into `Vec`s and checked with linear scans.
+    fn old_highlight_z_levels(
+        highlights: &[GridHighlight],
+        positions: &[GridPosition],
+    ) -> Vec<f32> {
+        let mut need_movement_z_level = vec![];
+        let mut need_selected_z_level = vec![];
+
+        for grid_highlight in highlights.iter() {
+            use GridHighlightType::*;
+            match grid_highlight.highlight_type {
+                PlayerUnitSelected => {
+                    need_selected_z_level.push(grid_highlight.pos);
+                }
+                Objective => {}
+                _ => {
+                    need_movement_z_level.push(grid_highlight.pos);
+                }
+            }
+        }
+
+        positions
+            .iter()
+            .map(|pos| {
+                if need_selected_z_level.contains(pos) {
+                    9.
+                } else if need_movement_z_level.contains(pos) {
+                    5.
+                } else {
+                    1.
+                }
+            })
+            .collect()
+    }
+
+    fn highlight_z_levels(
+        highlight_index: &HighlightIndex,
+        positions: &[GridPosition],
+    ) -> Vec<f32> {
+        positions
+            .iter()
+            .map(|pos| {
+                if highlight_index.selected.contains(pos) {
+                    9.
+                } else if highlight_index.raises_to_movement_z(pos) {
+                    5.
+                } else {
+                    1.
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn highlight_index_matches_linear_scan() {
+        use GridHighlightType::*;
+
+        let kinds = [
+            PlayerUnitMovement,
+            PlayerHover,
+            PlayerUnitSelected,
+            ClickFeedback,
+            Objective,
+            PathPreview,
+            PathPreviewOverBudget,
+        ];
+        // Every kind on its own tile along the first row, then overlapping pairs below.
+        let mut highlights = vec![];
+        for (x, highlight_type) in kinds.iter().enumerate() {
+            highlights.push(GridHighlight {
+                pos: GridPosition { x: x as u32, y: 0 },
+                highlight_type: *highlight_type,
+            });
+            highlights.push(GridHighlight {
+                pos: GridPosition { x: x as u32, y: 1 },
+                highlight_type: *highlight_type,
+            });
+            highlights.push(GridHighlight {
+                pos: GridPosition { x: x as u32, y: 1 },
+                highlight_type: kinds[(x + 1) % kinds.len()],
+            });
+        }
+
+        let mut world = World::default();
+        world.insert_resource(HighlightIndex::default());
+        for highlight in highlights.iter() {
+            world.spawn().insert(GridHighlight {
+                pos: highlight.pos,
+                highlight_type: highlight.highlight_type,
+            });
+        }
+        SystemStage::single(build_highlight_index.system()).run(&mut world);
+        let highlight_index = world.get_resource::<HighlightIndex>().unwrap();
+
+        let positions_of = |types: &[GridHighlightType]| {
+            highlights
+                .iter()
+                .filter(|highlight| types.contains(&highlight.highlight_type))
+                .map(|highlight| highlight.pos)
+                .collect::<HashSet<GridPosition>>()
+        };
+        assert_eq!(
+            highlight_index.selected,
+            positions_of(&[PlayerUnitSelected])
+        );
+        assert_eq!(
+            highlight_index.movement,
+            positions_of(&[PlayerUnitMovement])
+        );
+        assert_eq!(
+            highlight_index.hover,
+            positions_of(&[PlayerHover, ClickFeedback])
+        );
+        assert_eq!(highlight_index.objective, positions_of(&[Objective]));
+        assert_eq!(highlight_index.path_preview, positions_of(&[PathPreview]));
+        assert_eq!(
+            highlight_index.path_preview_over_budget,
+            positions_of(&[PathPreviewOverBudget])
+        );
+
+        let positions = (0..3)
+            .flat_map(|y| (0..=kinds.len() as u32).map(move |x| GridPosition { x, y }))
+            .collect::<Vec<GridPosition>>();
+        assert_eq!(
+            highlight_z_levels(highlight_index, &positions),
+            old_highlight_z_levels(&highlights, &positions)
+        );
+    }
+
+    /// Times the z-level lookups for every tile of a 64x64 map covered in movement
+    /// highlights, linear scan against `HighlightIndex`. Run with
+    /// `cargo test --release -- --ignored --nocapture` to see the timings.
+    #[test]
+    #[ignore]
+    fn highlight_index_lookup_on_large_map() {
+        use std::time::Instant;
+
+        let grid = test_grid(64, 64);
+        let positions = (0..grid.width * grid.height)
+            .map(|index| GridPosition::from_index(index, &grid))
+            .collect::<Vec<GridPosition>>();
+        let highlights = positions
+            .iter()
+            .map(|pos| GridHighlight {
+                pos: *pos,
+                highlight_type: GridHighlightType::PlayerUnitMovement,
+            })
+            .collect::<Vec<GridHighlight>>();
+
+        let now = Instant::now();
+        let old_z_levels = old_highlight_z_levels(&highlights, &positions);
+        let linear_scan_time = now.elapsed();
+
+        let mut world = World::default();
+        world.insert_resource(HighlightIndex::default());
+        for highlight in highlights.iter() {
+            world.spawn().insert(GridHighlight {
+                pos: highlight.pos,
+                highlight_type: highlight.highlight_type,
+            });
+        }
+        let mut stage = SystemStage::single(build_highlight_index.system());
+
+        let now = Instant::now();
+        stage.run(&mut world);
+        let highlight_index = world.get_resource::<HighlightIndex>().unwrap();
+        let z_levels = highlight_z_levels(highlight_index, &positions);
+        let index_time = now.elapsed();
+
+        println!(
+            "linear scan: {:?}, HighlightIndex: {:?}",
+            linear_scan_time, index_time
+        );
+        assert_eq!(z_levels, old_z_levels);
+    }
+
+    #[test]
+    fn world_to_tile_inverts_tile_to_world() {
+        let render_settings = RenderSettings {
+            camera_offset: Vec2::new(-100., 40.),
+            tile_gap: 3.,
+            ..test_render_settings()
+        };
+        let grid = test_grid(4, 3);
+        let stride = render_settings.tile_stride();
+
+        for y in 0..3 {
+            for x in 0..4 {
+                let pos = GridPosition { x, y };
+                let centre = tile_to_world(&pos, &render_settings);
+                assert_eq!(world_to_tile(centre, &render_settings, &grid), Some(pos));
+                assert_eq!(
+                    world_to_tile(centre + Vec2::new(stride / 2., 0.), &render_settings, &grid),
+                    None,
+                    "gap right of {:?}",
+                    pos
+                );
+            }
+        }
+
+        let past_corner = tile_to_world(&GridPosition { x: 4, y: 3 }, &render_settings);
+        assert_eq!(world_to_tile(past_corner, &render_settings, &grid), None);
+        let before_origin = tile_to_world(&GridPosition { x: 0, y: 0 }, &render_settings)
+            - Vec2::new(stride, stride);
+        assert_eq!(world_to_tile(before_origin, &render_settings, &grid), None);
+    }
+
+    #[test]
+    fn lerp_ends_on_tile_centres() {
+        let render_settings = test_render_settings();
+        let from = GridPosition { x: 1, y: 2 };
+        let to = GridPosition { x: 4, y: 2 };
+
+        for easing in [Easing::Linear, Easing::EaseInOut].iter() {
+            assert_eq!(
+                lerp_tile_positions(from, to, 0., *easing, &render_settings),
+                tile_to_world(&from, &render_settings)
+            );
+            assert_eq!(
+                lerp_tile_positions(from, to, 1., *easing, &render_settings),
+                tile_to_world(&to, &render_settings)
+            );
+        }
+    }
+
+    #[test]
+    fn ease_in_out_lags_then_leads_linear() {
+        let render_settings = test_render_settings();
+        let from = GridPosition { x: 0, y: 0 };
+        let to = GridPosition { x: 4, y: 0 };
+        let x_at =
+            |t: f32, easing: Easing| lerp_tile_positions(from, to, t, easing, &render_settings).x;
+
+        assert!(x_at(0.25, Easing::EaseInOut) < x_at(0.25, Easing::Linear));
+        assert!(x_at(0.75, Easing::EaseInOut) > x_at(0.75, Easing::Linear));
+        // The curve is symmetric, so both meet halfway.
+        assert_eq!(x_at(0.5, Easing::EaseInOut), x_at(0.5, Easing::Linear));
+    }
+
+    #[test]
+    fn hard_fields_fewer_slower_units_than_easy() {
+        let easy = PartyLoadout::for_difficulty(Difficulty::Easy);
+        let hard = PartyLoadout::for_difficulty(Difficulty::Hard);
+        let max_range = |loadout: &PartyLoadout| {
+            loadout
+                .units
+                .iter()
+                .map(|unit| unit.movement_range)
+                .max()
+                .unwrap()
+        };
+
+        assert!(hard.units.len() < easy.units.len());
+        assert!(max_range(&hard) < max_range(&easy));
+    }
+
+    #[test]
+    fn visible_tile_rect_follows_camera() {
+        let grid = test_grid(16, 16);
+        let window_size = Vec2::new(400., 300.);
+        let visible = |camera_offset: Vec2| {
+            let render_settings = RenderSettings {
+                camera_offset,
+                ..test_render_settings()
+            };
+            visible_tile_rect(&render_settings, window_size, &grid)
+        };
+
+        assert_eq!(
+            visible(Vec2::ZERO),
+            Some((GridPosition { x: 0, y: 0 }, GridPosition { x: 2, y: 1 }))
+        );
+        assert_eq!(
+            visible(Vec2::new(-600., -480.)),
+            Some((GridPosition { x: 3, y: 3 }, GridPosition { x: 7, y: 5 }))
+        );
+        assert_eq!(visible(Vec2::new(1000., 0.)), None);
+    }
+
+    #[test]
+    fn movement_highlights_match_valid_moves() {
+        let mut world = World::default();
+        world.insert_resource(test_grid(8, 8));
+        let unit_pos = GridPosition { x: 3, y: 3 };
+        let unit = world
+            .spawn()
+            .insert(Selectable {})
+            .insert(SelectedUnit)
+            .insert(unit_pos)
+            .insert(MovementRange {
+                range: 2,
+                flying: false,
+            })
+            .id();
+        let blocker_pos = GridPosition { x: 4, y: 3 };
+        world.spawn().insert(Selectable {}).insert(blocker_pos);
+
+        SystemStage::single(handle_player_unit_selection_movement_highlights.system())
+            .run(&mut world);
+
+        let mut highlighted = world
+            .query::<&GridHighlight>()
+            .iter(&world)
+            .filter(|highlight| highlight.highlight_type == GridHighlightType::PlayerUnitMovement)
+            .map(|highlight| highlight.pos)
+            .collect::<Vec<GridPosition>>();
+        highlighted.sort_by_key(GridPosition::row_major_key);
+
+        let valid_moves = rules::valid_moves(
+            unit_pos,
+            world.get::<MovementRange>(unit).unwrap(),
+            world.get_resource::<GameGrid>().unwrap(),
+            &[unit_pos, blocker_pos],
+        );
+        assert!(!valid_moves.contains(&blocker_pos));
+        assert_eq!(highlighted, valid_moves);
+    }
+
+    #[test]
+    fn path_preview_marks_steps_past_range_over_budget() {
+        use GridHighlightType::*;
+
+        let mut world = World::default();
+        world.insert_resource(test_grid(6, 1));
+        world
+            .spawn()
+            .insert(Selectable {})
+            .insert(SelectedUnit)
+            .insert(GridPosition { x: 0, y: 0 })
+            .insert(MovementRange {
+                range: 2,
+                flying: false,
+            });
+        world
+            .spawn()
+            .insert(GridTileTag)
+            .insert(Hoverable { hovered: true })
+            .insert(GridPosition { x: 4, y: 0 });
+
+        SystemStage::single(handle_path_preview_highlights.system()).run(&mut world);
+
+        let mut highlights = world
+            .query::<&GridHighlight>()
+            .iter(&world)
+            .map(|highlight| (highlight.pos.x, highlight.highlight_type))
+            .collect::<Vec<(u32, GridHighlightType)>>();
+        highlights.sort_by_key(|(x, _)| *x);
+        assert_eq!(
+            highlights,
+            vec![
+                (1, PathPreview),
+                (2, PathPreview),
+                (3, PathPreviewOverBudget),
+                (4, PathPreviewOverBudget),
+            ]
+        );
+    }
+
+    #[test]
+    fn movement_highlights_spawn_in_row_major_order() {
+        let mut world = World::default();
+        world.insert_resource(test_grid(8, 8));
+        world
+            .spawn()
+            .insert(Selectable {})
+            .insert(SelectedUnit)
+            .insert(GridPosition { x: 3, y: 3 })
+            .insert(MovementRange {
+                range: 1,
+                flying: false,
+            });
+
+        SystemStage::single(handle_player_unit_selection_movement_highlights.system())
+            .run(&mut world);
+
+        // Entity ids are handed out in spawn order in a fresh world.
+        let mut highlights = world
+            .query::<(Entity, &GridHighlight)>()
+            .iter(&world)
+            .map(|(entity, highlight)| (entity.id(), (highlight.pos.x, highlight.pos.y)))
+            .collect::<Vec<(u32, (u32, u32))>>();
+        highlights.sort_by_key(|(id, _)| *id);
+        assert_eq!(
+            highlights
+                .into_iter()
+                .map(|(_, pos)| pos)
+                .collect::<Vec<(u32, u32)>>(),
+            vec![(3, 2), (2, 3), (4, 3), (3, 4)]
+        );
+    }
+
+    /// Lets at least `seconds` of real time pass, then updates `Time` so systems see a
+    /// delta of at least that long. `Time` only reads the system clock.
+    fn advance_time(world: &mut World, seconds: f32) {
+        std::thread::sleep(std::time::Duration::from_secs_f32(seconds));
+        world.get_resource_mut::<Time>().unwrap().update();
+    }
+
+    #[test]
+    fn camera_script_reaches_each_target_when_its_step_ends() {
+        let mut world = World::default();
+        let render_settings = test_render_settings();
+        let grid = test_grid(16, 16);
+        let pan_target = GridPosition { x: 4, y: 4 };
+        let panned_offset = centered_camera_offset(&pan_target, 2., &render_settings, &grid);
+        let zoomed_offset = clamp_camera_offset(panned_offset * 1.5, 3., &render_settings, &grid);
+        world.insert_resource(render_settings);
+        world.insert_resource(grid);
+        world.insert_resource(CameraTransition::default());
+        world.insert_resource(CameraScript {
+            steps: vec![
+                CameraScriptStep::PanTo {
+                    pos: pan_target,
+                    seconds: 0.05,
+                },
+                CameraScriptStep::Zoom {
+                    tile_scale: 3.,
+                    seconds: 0.05,
+                },
+            ]
+            .into_iter()
+            .collect(),
+            current: None,
+        });
+        world.insert_resource(Time::default());
+        advance_time(&mut world, 0.);
+
+        let mut stage = SystemStage::single(run_camera_script.system());
+        let camera = |world: &World| {
+            let render_settings = world.get_resource::<RenderSettings>().unwrap();
+            (render_settings.camera_offset, render_settings.tile_scale)
+        };
+
+        stage.run(&mut world);
+        assert_eq!(camera(&world), (Vec2::ZERO, 2.));
+
+        advance_time(&mut world, 0.06);
+        stage.run(&mut world);
+        assert_eq!(camera(&world), (panned_offset, 2.));
+
+        advance_time(&mut world, 0.);
+        stage.run(&mut world);
+        assert_ne!(camera(&world).1, 3.);
+
+        advance_time(&mut world, 0.06);
+        stage.run(&mut world);
+        assert_eq!(camera(&world), (zoomed_offset, 3.));
+        assert!(!world.get_resource::<CameraScript>().unwrap().is_running());
+    }
+
+    /// A world with what the action sequence systems read, with `Time` started.
+    fn action_world() -> World {
+        let mut world = World::default();
+        world.insert_resource(Events::<AnimationFinished>::default());
+        world.insert_resource(SpriteSheets {
+            grid: Handle::default(),
+            myrrh: Handle::default(),
+        });
+        world.insert_resource(test_render_settings());
+        world.insert_resource(Time::default());
+        advance_time(&mut world, 0.);
+        world
+    }
+
+    /// The systems that play action sequences, ordered as in the app.
+    fn action_stage() -> SystemStage {
+        SystemStage::single_threaded()
+            .with_system(process_action_sequence.system().label("action_sequence"))
+            .with_system(animate_movement_tween.system().after("action_sequence"))
+            .with_system(animate_action.system().after("action_sequence"))
+            .with_system(
+                animate_destination_markers
+                    .system()
+                    .after("action_sequence"),
+            )
+    }
+
+    #[test]
+    fn destination_marker_lasts_while_unit_walks() {
+        let mut world = action_world();
+        let to = GridPosition { x: 1, y: 0 };
+        let unit = world
+            .spawn()
+            .insert(GridPosition { x: 0, y: 0 })
+            .insert(ActionSequence::new(vec![ActionStep::Walk(to)]))
+            .id();
+        let mut stage = action_stage();
+        let markers = |world: &mut World| {
+            world
+                .query::<&DestinationMarker>()
+                .iter(world)
+                .map(|marker| (marker.unit, marker.pos))
+                .collect::<Vec<(Entity, GridPosition)>>()
+        };
+
+        stage.run(&mut world);
+        assert!(world.get::<MovementTween>(unit).is_some());
+        assert_eq!(markers(&mut world), vec![(unit, to)]);
+
+        // The walk takes 0.1 seconds, the tween ends and reports it this frame.
+        advance_time(&mut world, 0.12);
+        stage.run(&mut world);
+        assert!(world.get::<MovementTween>(unit).is_none());
+
+        advance_time(&mut world, 0.);
+        stage.run(&mut world);
+        assert!(markers(&mut world).is_empty());
+        assert!(world.get::<ActionSequence>(unit).is_none());
+    }
+
+    #[test]
+    fn idle_squash_is_unscaled_at_frame_boundaries() {
+        assert_eq!(idle_squash(0.), Vec2::ONE);
+        assert!((idle_squash(1.) - Vec2::ONE).abs().max_element() < 1e-5);
+    }
+
+    #[test]
+    fn idle_squash_changes_smoothly() {
+        const SAMPLES: u32 = 100;
+
+        // Stepping across the end of one frame into the start of the next is included.
+        let mut previous = idle_squash(1. - 1. / SAMPLES as f32);
+        for i in 0..=SAMPLES {
+            let squash = idle_squash(i as f32 / SAMPLES as f32);
+            assert!(
+                (squash - previous).abs().max_element() < 0.002,
+                "jump from {:?} to {:?} at sample {}",
+                previous,
+                squash,
+                i
+            );
+            previous = squash;
+        }
+        assert!(idle_squash(0.5).x > 1.);
+        assert!(idle_squash(0.5).y < 1.);
+    }
+
+    #[test]
+    fn action_sequence_plays_steps_in_order() {
+        let mut world = action_world();
+        let unit = world
+            .spawn()
+            .insert(GridPosition { x: 0, y: 0 })
+            .insert(TextureAtlasSprite::new(0))
+            .insert(ActionSequence::new(vec![
+                ActionStep::Walk(GridPosition { x: 1, y: 0 }),
+                ActionStep::Animate(AnimationRange::from_start_end(0, 1)),
+            ]))
+            .id();
+        let mut stage = action_stage();
+
+        let mut playing = vec![];
+        for _ in 0..20 {
+            stage.run(&mut world);
+            let step = if world.get::<MovementTween>(unit).is_some() {
+                "walk"
+            } else if world.get::<ActionAnimation>(unit).is_some() {
+                "animate"
+            } else {
+                "between steps"
+            };
+            if playing.last() != Some(&step) {
+                playing.push(step);
+            }
+            if world.get::<ActionSequence>(unit).is_none() {
+                break;
+            }
+            advance_time(&mut world, 0.12);
+        }
+
+        assert_eq!(
+            playing,
+            vec!["walk", "between steps", "animate", "between steps"]
+        );
+        assert!(world.get::<ActionSequence>(unit).is_none());
+        assert_eq!(
+            *world.get::<GridPosition>(unit).unwrap(),
+            GridPosition { x: 1, y: 0 }
+        );
+    }
+
+    /// `click_world` plus what `handle_mouse_interactions` reads: an 800x600 window with
+    /// the cursor in its centre and an unpressed mouse.
+    fn mouse_world(game_rules: GameRules) -> World {
+        let mut world = click_world();
+        let mut window = Window::new(
+            bevy::window::WindowId::primary(),
+            &WindowDescriptor::default(),
+            800,
+            600,
+            1.,
+            None,
+        );
+        window.update_cursor_position_from_backend(Some(Vec2::new(400., 300.)));
+        let mut windows = Windows::default();
+        windows.add(window);
+        world.insert_resource(windows);
+        world.insert_resource(Input::<MouseButton>::default());
+        world.insert_resource(game_rules);
+        world.insert_resource(CameraScript::default());
+        world.insert_resource(QueuedClick::default());
+        world
+    }
+
+    /// Spawns a selectable unit under the centre of the screen.
+    fn spawn_unit_under_cursor(world: &mut World) -> Entity {
+        world
+            .spawn()
+            .insert(MouseInteractible {
+                bounding_box: Rect {
+                    left: -10.,
+                    right: 10.,
+                    bottom: -10.,
+                    top: 10.,
+                },
+                z: 10,
+            })
+            .insert(Clickable::default())
+            .insert(Selectable {})
+            .insert(GridPosition { x: 0, y: 0 })
+            .id()
+    }
+
+    /// Runs one frame of mouse input into click resolution, pressing the left button
+    /// first if `press` is set.
+    fn run_mouse_frame(world: &mut World, press: bool) {
+        {
+            let mut mouse_input = world.get_resource_mut::<Input<MouseButton>>().unwrap();
+            mouse_input.update();
+            if press {
+                mouse_input.press(MouseButton::Left);
+            }
+        }
+        SystemStage::single_threaded()
+            .with_system(handle_mouse_interactions.system().label("mouse_input"))
+            .with_system(handle_clicks.system().after("mouse_input"))
+            .run(world);
+    }
+
+    #[test]
+    fn click_during_animation_is_dropped() {
+        let mut world = mouse_world(GameRules {
+            queue_clicks_during_animation: false,
+            ..Default::default()
+        });
+        let unit = spawn_unit_under_cursor(&mut world);
+        let animating = world
+            .spawn()
+            .insert(Selectable {})
+            .insert(ActionSequence::default())
+            .id();
+
+        run_mouse_frame(&mut world, true);
+        assert!(world.get::<SelectedUnit>(unit).is_none());
+
+        world.entity_mut(animating).remove::<ActionSequence>();
+        run_mouse_frame(&mut world, false);
+        assert!(world.get::<SelectedUnit>(unit).is_none());
+    }
+
+    #[test]
+    fn click_during_animation_is_replayed_afterwards() {
+        let mut world = mouse_world(GameRules {
+            queue_clicks_during_animation: true,
+            ..Default::default()
+        });
+        let unit = spawn_unit_under_cursor(&mut world);
+        let animating = world
+            .spawn()
+            .insert(Selectable {})
+            .insert(ActionSequence::default())
+            .id();
+
+        run_mouse_frame(&mut world, true);
+        assert!(world.get::<SelectedUnit>(unit).is_none());
+
+        run_mouse_frame(&mut world, false);
+        assert!(world.get::<SelectedUnit>(unit).is_none());
+
+        world.entity_mut(animating).remove::<ActionSequence>();
+        run_mouse_frame(&mut world, false);
+        assert!(world.get::<SelectedUnit>(unit).is_some());
+        assert!(world
+            .get_resource::<QueuedClick>()
+            .unwrap()
+            .position
+            .is_none());
+    }
+
+    #[test]
+    fn empty_ground_click_with_nothing_selected_ripples() {
+        let mut world = click_world();
+        let tile = GridPosition { x: 5, y: 5 };
+        world
+            .spawn()
+            .insert(Clickable { clicked: true })
+            .insert(GridTileTag)
+            .insert(tile);
+
+        SystemStage::single(handle_clicks.system()).run(&mut world);
+
+        let ripples = world
+            .query_filtered::<&GridHighlight, With<ClickRipple>>()
+            .iter(&world)
+            .map(|highlight| (highlight.pos, highlight.highlight_type))
+            .collect::<Vec<(GridPosition, GridHighlightType)>>();
+        assert_eq!(ripples, vec![(tile, GridHighlightType::ClickFeedback)]);
+        assert!(sent_rejections(&world).is_empty());
+    }
+
+    #[test]
+    fn click_off_the_grid_deselects() {
+        let mut world = click_world();
+        world.insert_resource(LastClick {
+            was_handled: false,
+            this_frame: true,
+        });
+        let unit = world
+            .spawn()
+            .insert(Clickable::default())
+            .insert(Selectable {})
+            .insert(SelectedUnit)
+            .insert(GridPosition { x: 2, y: 2 })
+            .id();
+
+        SystemStage::single(handle_clicks.system()).run(&mut world);
+
+        assert!(world.get::<SelectedUnit>(unit).is_none());
+        assert!(sent_rejections(&world).is_empty());
+    }
+
+    /// A world with a selectable unit that has just been deselected and remembered by
+    /// `track_last_selected`.
+    fn recall_world() -> (World, Entity) {
+        let mut world = World::default();
+        world.insert_resource(LastSelected::default());
+        world.insert_resource(TurnState::default());
+        world.insert_resource(CameraScript::default());
+        world.insert_resource(Input::<KeyCode>::default());
+        let unit = world
+            .spawn()
+            .insert(Selectable {})
+            .insert(SelectedUnit)
+            .id();
+
+        let mut track_stage = SystemStage::single(track_last_selected.system());
+        // The first run sees TurnState as changed and only resets.
+        track_stage.run(&mut world);
+        world.entity_mut(unit).remove::<SelectedUnit>();
+        track_stage.run(&mut world);
+        assert_eq!(world.get_resource::<LastSelected>().unwrap().0, Some(unit));
+
+        (world, unit)
+    }
+
+    fn press_recall(world: &mut World) {
+        {
+            let mut keyboard_input = world.get_resource_mut::<Input<KeyCode>>().unwrap();
+            keyboard_input.update();
+            keyboard_input.press(KeyCode::R);
+        }
+        SystemStage::single(recall_last_selected.system()).run(world);
+    }
+
+    #[test]
+    fn recall_reselects_last_deselected_unit() {
+        let (mut world, unit) = recall_world();
+
+        press_recall(&mut world);
+
+        assert!(world.get::<SelectedUnit>(unit).is_some());
+        assert_eq!(world.get_resource::<LastSelected>().unwrap().0, None);
+    }
+
+    #[test]
+    fn recall_waits_for_animations() {
+        let (mut world, unit) = recall_world();
+        let animating = world
+            .spawn()
+            .insert(Selectable {})
+            .insert(ActionSequence::default())
+            .id();
+
+        press_recall(&mut world);
+        assert!(world.get::<SelectedUnit>(unit).is_none());
+
+        world.entity_mut(animating).remove::<ActionSequence>();
+        press_recall(&mut world);
+        assert!(world.get::<SelectedUnit>(unit).is_some());
+    }
+
+    #[test]
+    fn rejected_recall_keeps_last_selected() {
+        let (mut world, unit) = recall_world();
+        world.get_resource_mut::<TurnState>().unwrap().mode = TurnMode::Initiative;
+
+        press_recall(&mut world);
+        assert!(world.get::<SelectedUnit>(unit).is_none());
+        assert_eq!(world.get_resource::<LastSelected>().unwrap().0, Some(unit));
+
+        world.get_resource_mut::<TurnState>().unwrap().active_unit = Some(unit);
+        press_recall(&mut world);
+        assert!(world.get::<SelectedUnit>(unit).is_some());
+    }
+
+    #[test]
+    fn turn_ends_when_timer_runs_out() {
+        let mut world = World::default();
+        world.insert_resource(Events::<EndTurnEvent>::default());
+        world.insert_resource(Events::<TurnStartEvent>::default());
+        world.insert_resource(GameRules {
+            turn_time_limit: Some(1.),
+            ..Default::default()
+        });
+        world.insert_resource(TurnTimer { remaining: 1. });
+        world.insert_resource(TurnState {
+            turn: Turn::Player,
+            turn_number: 1,
+            ..Default::default()
+        });
+        world.insert_resource(Time::default());
+        advance_time(&mut world, 0.);
+        let unit = world
+            .spawn()
+            .insert(Selectable {})
+            .insert(SelectedUnit)
+            .id();
+        let mut stage = SystemStage::single_threaded()
+            .with_system(tick_turn_timer.system().before("advance_turn"))
+            .with_system(advance_turn.system().label("advance_turn"));
+
+        stage.run(&mut world);
+        assert_eq!(
+            world.get_resource::<TurnState>().unwrap().turn,
+            Turn::Player
+        );
+
+        advance_time(&mut world, 1.05);
+        stage.run(&mut world);
+
+        let end_turn_events = world.get_resource::<Events<EndTurnEvent>>().unwrap();
+        assert_eq!(
+            end_turn_events.get_reader().iter(end_turn_events).count(),
+            1
+        );
+        assert_eq!(world.get_resource::<TurnState>().unwrap().turn, Turn::Enemy);
+        assert!(world.get::<SelectedUnit>(unit).is_none());
+    }
+
+    #[test]
+    fn selected_animation_continues_into_chained_loop() {
+        let mut world = World::default();
+        world.insert_resource(Time::default());
+        advance_time(&mut world, 0.);
+        let unit = world
+            .spawn()
+            .insert(SelectedUnit)
+            .insert(TextureAtlasSprite::new(0))
+            .insert(SelectedAnimation {
+                animation: Some(AnimationRange::from_start_end(0, 2)),
+                should_loop: false,
+                timer: Timer::from_seconds(0.01, true),
+                chained: Some(AnimationRange::from_start_end(5, 6)),
+                ..Default::default()
+            })
+            .id();
+        let mut stage = SystemStage::single(animate_selected.system());
+
+        let mut frames = vec![];
+        for _ in 0..8 {
+            advance_time(&mut world, 0.02);
+            stage.run(&mut world);
+            frames.push(world.get::<TextureAtlasSprite>(unit).unwrap().index);
+        }
+
+        assert_eq!(frames, vec![0, 1, 2, 5, 6, 5, 6, 5]);
+    }
+
+    #[test]
+    fn objective_highlight_stays_until_tile_is_reached() {
+        let target = GridPosition { x: 3, y: 3 };
+        let mut world = World::default();
+        world.insert_resource(Scenario {
+            objectives: vec![ScenarioObjective {
+                objective: Objective::ReachTile(target),
+                completed: false,
+            }],
+        });
+        let unit = world
+            .spawn()
+            .insert(Selectable {})
+            .insert(GridPosition { x: 0, y: 0 })
+            .id();
+        let mut stage = SystemStage::single_threaded()
+            .with_system(update_objectives.system().label("objectives"))
+            .with_system(
+                handle_objective_grid_highlights
+                    .system()
+                    .after("objectives"),
+            );
+        let objective_highlights = |world: &mut World| {
+            world
+                .query::<&GridHighlight>()
+                .iter(world)
+                .filter(|highlight| highlight.highlight_type == GridHighlightType::Objective)
+                .map(|highlight| highlight.pos)
+                .collect::<Vec<GridPosition>>()
+        };
+
+        stage.run(&mut world);
+        stage.run(&mut world);
+        assert_eq!(objective_highlights(&mut world), vec![target]);
+
+        *world.get_mut::<GridPosition>(unit).unwrap() = target;
+        stage.run(&mut world);
+        assert!(objective_highlights(&mut world).is_empty());
+        assert!(world.get_resource::<Scenario>().unwrap().objectives[0].completed);
+    }
+
+    #[test]
+    fn camera_bookmark_restores_view() {
+        let mut world = World::default();
+        world.insert_resource(RenderSettings {
+            camera_offset: Vec2::new(-240., -360.),
+            ..test_render_settings()
+        });
+        world.insert_resource(test_grid(16, 16));
+        world.insert_resource(Input::<KeyCode>::default());
+        world.insert_resource(CameraBookmarks::default());
+        world.insert_resource(CameraTransition::default());
+        world.insert_resource(CameraScript::default());
+        world.insert_resource(Time::default());
+        advance_time(&mut world, 0.);
+        let mut stage = SystemStage::single_threaded()
+            .with_system(handle_camera_bookmarks.system().label("bookmarks"))
+            .with_system(lerp_camera.system().after("bookmarks"));
+        let press = |world: &mut World, keys: &[KeyCode]| {
+            let mut keyboard_input = world.get_resource_mut::<Input<KeyCode>>().unwrap();
+            keyboard_input.update();
+            for key in [KeyCode::LShift, KeyCode::Key1, KeyCode::Key2].iter() {
+                keyboard_input.release(*key);
+            }
+            for key in keys.iter() {
+                keyboard_input.press(*key);
             }
+        };
+        let camera = |world: &World| {
+            let render_settings = world.get_resource::<RenderSettings>().unwrap();
+            (render_settings.camera_offset, render_settings.tile_scale)
+        };
+
+        press(&mut world, &[KeyCode::LShift, KeyCode::Key1]);
+        stage.run(&mut world);
+
+        {
+            let mut render_settings = world.get_resource_mut::<RenderSettings>().unwrap();
+            render_settings.camera_offset = Vec2::ZERO;
+            render_settings.tile_scale = 4.;
+        }
+        press(&mut world, &[KeyCode::Key1]);
+        // Long enough for the transition to land in one frame.
+        advance_time(&mut world, 0.2);
+        stage.run(&mut world);
+        assert_eq!(camera(&world), (Vec2::new(-240., -360.), 2.));
+
+        // A bookmark past the zoom limit and the grid edges is recalled clamped to them.
+        world.get_resource_mut::<CameraBookmarks>().unwrap().slots[1] =
+            Some((Vec2::new(500., -99999.), 20.));
+        press(&mut world, &[KeyCode::Key2]);
+        advance_time(&mut world, 0.2);
+        stage.run(&mut world);
+        // 15 tiles of a 600 unit stride at the maximum tile scale of 10.
+        assert_eq!(camera(&world), (Vec2::new(0., -9000.), MAX_TILE_SCALE));
+    }
+
+    #[test]
+    fn roster_click_selects_and_centres_on_unit() {
+        let mut world = World::default();
+        world.insert_resource(TurnState::default());
+        world.insert_resource(test_render_settings());
+        world.insert_resource(test_grid(16, 16));
+        world.insert_resource(CameraScript::default());
+        world.insert_resource(CameraTransition::default());
+        world.insert_resource(Events::<RejectEvent>::default());
+        let unit_pos = GridPosition { x: 5, y: 4 };
+        let unit = world.spawn().insert(Selectable {}).insert(unit_pos).id();
+        world
+            .spawn()
+            .insert(Interaction::Clicked)
+            .insert(RosterEntry { unit });
+
+        SystemStage::single(handle_roster_clicks.system()).run(&mut world);
+
+        assert!(world.get::<SelectedUnit>(unit).is_some());
+        let expected_offset = centered_camera_offset(
+            &unit_pos,
+            2.,
+            world.get_resource::<RenderSettings>().unwrap(),
+            world.get_resource::<GameGrid>().unwrap(),
+        );
+        assert_eq!(
+            world.get_resource::<CameraTransition>().unwrap().target,
+            Some((expected_offset, 2.))
+        );
+    }
+
+    #[test]
+    fn map_clear_color_is_applied() {
+        let mut world = World::default();
+        let clear_color = Color::rgb(0.1, 0.2, 0.3);
+        world.insert_resource(MapAmbient {
+            clear_color,
+            ..Default::default()
+        });
+
+        SystemStage::single(apply_map_clear_color.system()).run(&mut world);
+
+        assert_eq!(world.get_resource::<ClearColor>().unwrap().0, clear_color);
+    }
+
+    #[test]
+    fn map_tint_is_applied_to_units() {
+        let mut world = World::default();
+        let tint = Color::rgb(0.3, 0.3, 0.6);
+        world.insert_resource(MapAmbient {
+            tint,
+            ..Default::default()
+        });
+        let unit = world
+            .spawn()
+            .insert(Selectable {})
+            .insert(TextureAtlasSprite::new(3))
+            .id();
+
+        SystemStage::single(tint_units.system()).run(&mut world);
+
+        let sprite = world.get::<TextureAtlasSprite>(unit).unwrap();
+        assert_eq!((sprite.index, sprite.color), (3, tint));
+    }
+
+    #[test]
+    fn decorative_tiles_are_not_interactive() {
+        let decorative = GridPosition { x: 0, y: 0 };
+        let mut world = mouse_world(GameRules::default());
+        world.insert_resource(GameGrid {
+            width: 2,
+            height: 1,
+            decorative_tiles: vec![decorative],
+        });
+        world.insert_resource(SpriteSheets {
+            grid: Handle::default(),
+            myrrh: Handle::default(),
+        });
+        world.insert_resource(test_render_settings());
+
+        SystemStage::single(setup_grid_tiles.system()).run(&mut world);
+
+        let mut tiles = world.query_filtered::<(Entity, &GridPosition), With<GridTileTag>>();
+        let decorative_tile = tiles
+            .iter(&world)
+            .find(|(_, pos)| **pos == decorative)
+            .map(|(entity, _)| entity)
+            .unwrap();
+        assert!(world.get::<MouseInteractible>(decorative_tile).is_none());
+        assert!(world.get::<Clickable>(decorative_tile).is_none());
+        assert!(world.get::<Hoverable>(decorative_tile).is_none());
+        let interactive_tiles = world
+            .query_filtered::<&GridPosition, With<MouseInteractible>>()
+            .iter(&world)
+            .copied()
+            .collect::<Vec<GridPosition>>();
+        assert_eq!(interactive_tiles, vec![GridPosition { x: 1, y: 0 }]);
+
+        // Make every interactive entity cover the cursor, then click.
+        for mut mouse_interactible in world.query::<&mut MouseInteractible>().iter_mut(&mut world) {
+            mouse_interactible.bounding_box = Rect {
+                left: -1000.,
+                right: 1000.,
+                bottom: -1000.,
+                top: 1000.,
+            };
         }
+        run_mouse_frame(&mut world, true);
+
+        assert!(world.get_resource::<LastClick>().unwrap().was_handled);
+        let clicked = world
+            .query::<(&GridPosition, &Clickable)>()
+            .iter(&world)
+            .filter(|(_, clickable)| clickable.clicked)
+            .map(|(pos, _)| *pos)
+            .collect::<Vec<GridPosition>>();
+        assert_eq!(clicked, vec![GridPosition { x: 1, y: 0 }]);
     }
 }
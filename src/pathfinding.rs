@@ -0,0 +1,418 @@
+use std::collections::VecDeque;
+
+use crate::{GameGrid, GridPosition};
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(not(test), allow(dead_code))]
+pub enum MovementMetric {
+    /// Orthogonal steps only, diagonals cost two.
+    Manhattan,
+    /// Diagonal steps cost the same as orthogonal ones.
+    Chebyshev,
+}
+
+#[cfg_attr(not(test), allow(dead_code))]
+impl MovementMetric {
+    pub fn dist(&self, a: &GridPosition, b: &GridPosition) -> u32 {
+        let dx = (a.x as i32 - b.x as i32).abs() as u32;
+        let dy = (a.y as i32 - b.y as i32).abs() as u32;
+
+        match self {
+            MovementMetric::Manhattan => dx + dy,
+            MovementMetric::Chebyshev => dx.max(dy),
+        }
+    }
+}
+
+/// Every tile within `range` of `center` under `metric`, including `center`, clipped to `grid`.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn tiles_in_range(
+    center: GridPosition,
+    range: u32,
+    metric: MovementMetric,
+    grid: &GameGrid,
+) -> Vec<GridPosition> {
+    let min_x = center.x.saturating_sub(range);
+    let min_y = center.y.saturating_sub(range);
+    let max_x = (center.x.saturating_add(range) as usize).min(grid.width.saturating_sub(1)) as u32;
+    let max_y = (center.y.saturating_add(range) as usize).min(grid.height.saturating_sub(1)) as u32;
+
+    let mut tiles = vec![];
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let pos = GridPosition { x, y };
+            if metric.dist(&center, &pos) <= range {
+                tiles.push(pos);
+            }
+        }
+    }
+
+    tiles
+}
+
+/// Calls `f` for every occupant standing on a tile in range of `center`.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn for_each_occupant_in_range<T>(
+    center: GridPosition,
+    range: u32,
+    metric: MovementMetric,
+    grid: &GameGrid,
+    occupants: impl IntoIterator<Item = (T, GridPosition)>,
+    mut f: impl FnMut(T, GridPosition),
+) {
+    for (occupant, pos) in occupants {
+        let on_grid = (pos.x as usize) < grid.width && (pos.y as usize) < grid.height;
+        if on_grid && metric.dist(&center, &pos) <= range {
+            f(occupant, pos);
+        }
+    }
+}
+
+/// Orthogonal neighbours of `pos` that lie inside `grid`.
+pub fn neighbours(pos: &GridPosition, grid: &GameGrid) -> Vec<GridPosition> {
+    let mut neighbours = vec![];
+
+    if pos.x > 0 {
+        neighbours.push(GridPosition {
+            x: pos.x - 1,
+            y: pos.y,
+        });
+    }
+    if pos.y > 0 {
+        neighbours.push(GridPosition {
+            x: pos.x,
+            y: pos.y - 1,
+        });
+    }
+    if (pos.x as usize) + 1 < grid.width {
+        neighbours.push(GridPosition {
+            x: pos.x + 1,
+            y: pos.y,
+        });
+    }
+    if (pos.y as usize) + 1 < grid.height {
+        neighbours.push(GridPosition {
+            x: pos.x,
+            y: pos.y + 1,
+        });
+    }
+
+    neighbours
+}
+
+/// Dense per-tile storage for one grid, indexed by `GridPosition::to_index`.
+///
+/// Cheaper than a `HashMap<GridPosition, T>` for searches that touch a large part of a
+/// big map.
+pub struct GridStore<'a, T> {
+    grid: &'a GameGrid,
+    cells: Vec<Option<T>>,
+}
+
+impl<'a, T> GridStore<'a, T> {
+    pub fn new(grid: &'a GameGrid) -> Self {
+        GridStore {
+            grid,
+            cells: (0..grid.width * grid.height).map(|_| None).collect(),
+        }
+    }
+
+    fn in_bounds(&self, pos: &GridPosition) -> bool {
+        (pos.x as usize) < self.grid.width && (pos.y as usize) < self.grid.height
+    }
+
+    pub fn get(&self, pos: &GridPosition) -> Option<&T> {
+        if !self.in_bounds(pos) {
+            return None;
+        }
+
+        self.cells[pos.to_index(self.grid)].as_ref()
+    }
+
+    pub fn contains(&self, pos: &GridPosition) -> bool {
+        self.get(pos).is_some()
+    }
+
+    /// Panics if `pos` is outside the grid.
+    pub fn insert(&mut self, pos: GridPosition, value: T) {
+        assert!(
+            self.in_bounds(&pos),
+            "({}, {}) is outside the grid",
+            pos.x,
+            pos.y
+        );
+
+        let index = pos.to_index(self.grid);
+        self.cells[index] = Some(value);
+    }
+
+    /// Occupied cells in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = (GridPosition, &T)> + '_ {
+        let grid = self.grid;
+        self.cells
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, cell)| {
+                cell.as_ref()
+                    .map(|value| (GridPosition::from_index(index, grid), value))
+            })
+    }
+}
+
+/// Result of a breadth-first search from a single origin: the step cost to every reached
+/// tile, and the tile each one was first reached from.
+pub struct Reachability<'a> {
+    pub start: GridPosition,
+    pub costs: GridStore<'a, u32>,
+    pub came_from: GridStore<'a, GridPosition>,
+}
+
+impl<'a> Reachability<'a> {
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn cost_to(&self, target: &GridPosition) -> Option<u32> {
+        self.costs.get(target).copied()
+    }
+
+    /// The tiles walked from `start` to `target`, excluding `start` and ending with
+    /// `target`. `None` if `target` wasn't reached.
+    pub fn path_to(&self, target: GridPosition) -> Option<Vec<GridPosition>> {
+        if !self.costs.contains(&target) {
+            return None;
+        }
+
+        let mut path = vec![];
+        let mut pos = target;
+        while pos != self.start {
+            path.push(pos);
+            pos = self.came_from.get(&pos).copied()?;
+        }
+        path.reverse();
+
+        Some(path)
+    }
+
+    /// Every reached tile other than `start`, in row-major order.
+    pub fn tiles(&self) -> impl Iterator<Item = GridPosition> + '_ {
+        let start = self.start;
+        self.costs
+            .iter()
+            .map(|(pos, _)| pos)
+            .filter(move |pos| *pos != start)
+    }
+}
+
+/// Searches outward from `start` up to `range` steps, never entering a tile in `blocked`.
+pub fn reachability_tree<'a>(
+    start: GridPosition,
+    range: u32,
+    grid: &'a GameGrid,
+    blocked: &[GridPosition],
+) -> Reachability<'a> {
+    let mut costs = GridStore::new(grid);
+    let mut came_from = GridStore::new(grid);
+    let mut frontier = VecDeque::new();
+
+    costs.insert(start, 0);
+    frontier.push_back(start);
+
+    while let Some(pos) = frontier.pop_front() {
+        let cost = *costs.get(&pos).unwrap();
+        if cost == range {
+            continue;
+        }
+
+        for neighbour in neighbours(&pos, grid) {
+            if !costs.contains(&neighbour) && !blocked.contains(&neighbour) {
+                costs.insert(neighbour, cost + 1);
+                came_from.insert(neighbour, pos);
+                frontier.push_back(neighbour);
+            }
+        }
+    }
+
+    Reachability {
+        start,
+        costs,
+        came_from,
+    }
+}
+
+/// Every tile within `range` steps of `start`, excluding `start` itself.
+///
+/// The search only ever expands into in-bounds neighbours, so a unit near an edge or
+/// corner gets a clipped range rather than positions outside `grid`.
+pub fn reachable_tiles(start: GridPosition, range: u32, grid: &GameGrid) -> Vec<GridPosition> {
+    reachability_tree(start, range, grid, &[]).tiles().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Instant};
+
+    use super::*;
+
+    fn test_grid(width: usize, height: usize) -> GameGrid {
+        GameGrid {
+            width,
+            height,
+            decorative_tiles: vec![],
+        }
+    }
+
+    fn positions(coords: &[(u32, u32)]) -> Vec<GridPosition> {
+        coords.iter().map(|&(x, y)| GridPosition { x, y }).collect()
+    }
+
+    #[test]
+    fn manhattan_range_is_clipped_at_grid_edge() {
+        let grid = test_grid(4, 4);
+        assert_eq!(
+            tiles_in_range(
+                GridPosition { x: 0, y: 0 },
+                2,
+                MovementMetric::Manhattan,
+                &grid
+            ),
+            positions(&[(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (0, 2)])
+        );
+    }
+
+    #[test]
+    fn chebyshev_range_is_clipped_at_grid_edge() {
+        let grid = test_grid(4, 4);
+        assert_eq!(
+            tiles_in_range(
+                GridPosition { x: 3, y: 3 },
+                2,
+                MovementMetric::Chebyshev,
+                &grid
+            ),
+            positions(&[
+                (1, 1),
+                (2, 1),
+                (3, 1),
+                (1, 2),
+                (2, 2),
+                (3, 2),
+                (1, 3),
+                (2, 3),
+                (3, 3)
+            ])
+        );
+    }
+
+    #[test]
+    fn occupants_in_range_skip_far_and_off_grid_positions() {
+        let grid = test_grid(4, 4);
+        let occupants = vec![
+            ("centre", GridPosition { x: 1, y: 1 }),
+            ("diagonal", GridPosition { x: 2, y: 2 }),
+            ("far", GridPosition { x: 3, y: 3 }),
+            ("off grid", GridPosition { x: 4, y: 1 }),
+        ];
+
+        let mut found = vec![];
+        for_each_occupant_in_range(
+            GridPosition { x: 2, y: 1 },
+            2,
+            MovementMetric::Manhattan,
+            &grid,
+            occupants,
+            |name, _| found.push(name),
+        );
+
+        assert_eq!(found, vec!["centre", "diagonal"]);
+    }
+
+    #[test]
+    fn reachable_tiles_stay_inside_grid_from_corners() {
+        let grid = test_grid(3, 3);
+        assert_eq!(
+            reachable_tiles(GridPosition { x: 0, y: 0 }, 2, &grid),
+            positions(&[(1, 0), (2, 0), (0, 1), (1, 1), (0, 2)])
+        );
+        assert_eq!(
+            reachable_tiles(GridPosition { x: 2, y: 2 }, 1, &grid),
+            positions(&[(2, 1), (1, 2)])
+        );
+    }
+
+    #[test]
+    fn path_routes_around_blocked_tile() {
+        let grid = test_grid(5, 5);
+        let start = GridPosition { x: 0, y: 2 };
+        let target = GridPosition { x: 2, y: 2 };
+        let blocked = GridPosition { x: 1, y: 2 };
+        let reachability = reachability_tree(start, 6, &grid, &[blocked]);
+
+        let path = reachability.path_to(target).unwrap();
+        assert_eq!(reachability.cost_to(&target), Some(4));
+        assert_eq!(path.len() as u32, reachability.cost_to(&target).unwrap());
+        assert_eq!(path.last(), Some(&target));
+        assert!(!path.contains(&blocked));
+        let mut previous = start;
+        for pos in path.iter() {
+            assert_eq!(previous.dist(pos), 1, "{:?} to {:?}", previous, pos);
+            previous = *pos;
+        }
+    }
+
+    /// `reachability_tree` as it was before `GridStore`, keeping costs in a `HashMap`.
+    fn hash_map_costs(
+        start: GridPosition,
+        range: u32,
+        grid: &GameGrid,
+    ) -> HashMap<GridPosition, u32> {
+        let mut costs = HashMap::new();
+        let mut frontier = VecDeque::new();
+
+        costs.insert(start, 0);
+        frontier.push_back(start);
+
+        while let Some(pos) = frontier.pop_front() {
+            let cost = costs[&pos];
+            if cost == range {
+                continue;
+            }
+
+            for neighbour in neighbours(&pos, grid) {
+                if !costs.contains_key(&neighbour) {
+                    costs.insert(neighbour, cost + 1);
+                    frontier.push_back(neighbour);
+                }
+            }
+        }
+
+        costs
+    }
+
+    /// Compares a search over a whole 256x256 map against the `HashMap` version. Run with
+    /// `cargo test --release -- --ignored --nocapture` to see the timings.
+    #[test]
+    #[ignore]
+    fn grid_store_matches_hash_map_on_large_map() {
+        let grid = GameGrid {
+            width: 256,
+            height: 256,
+            decorative_tiles: vec![],
+        };
+        let start = GridPosition { x: 128, y: 128 };
+
+        let now = Instant::now();
+        let hash_map = hash_map_costs(start, 512, &grid);
+        let hash_map_time = now.elapsed();
+
+        let now = Instant::now();
+        let reachability = reachability_tree(start, 512, &grid, &[]);
+        let grid_store_time = now.elapsed();
+
+        println!(
+            "HashMap: {:?}, GridStore: {:?}",
+            hash_map_time, grid_store_time
+        );
+        assert_eq!(hash_map.len(), grid.width * grid.height);
+        for (pos, cost) in hash_map.iter() {
+            assert_eq!(reachability.cost_to(pos), Some(*cost));
+        }
+    }
+}
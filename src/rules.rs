@@ -0,0 +1,45 @@
+//! What the player is allowed to do, shared by the UI and anything else issuing orders.
+
+use crate::{pathfinding, GameGrid, GridPosition, MovementRange};
+
+/// Every tile a unit at `unit_pos` may be ordered to move to, the same set the movement
+/// highlights show, ordered by row then column so highlights always spawn in the same order.
+/// Decorative tiles can't be clicked, so they're never valid moves.
+pub fn valid_moves(
+    unit_pos: GridPosition,
+    movement_range: &MovementRange,
+    grid: &GameGrid,
+    occupied: &[GridPosition],
+) -> Vec<GridPosition> {
+    let mut moves = pathfinding::reachable_tiles(unit_pos, movement_range.range, grid)
+        .into_iter()
+        .filter(|pos| !occupied.contains(pos) && !grid.decorative_tiles.contains(pos))
+        .collect::<Vec<GridPosition>>();
+    moves.sort_by_key(GridPosition::row_major_key);
+
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decorative_tiles_are_not_valid_moves() {
+        let decorative = GridPosition { x: 1, y: 0 };
+        let grid = GameGrid {
+            width: 3,
+            height: 1,
+            decorative_tiles: vec![decorative],
+        };
+        let movement_range = MovementRange {
+            range: 2,
+            flying: false,
+        };
+
+        assert_eq!(
+            valid_moves(GridPosition { x: 0, y: 0 }, &movement_range, &grid, &[]),
+            vec![GridPosition { x: 2, y: 0 }]
+        );
+    }
+}